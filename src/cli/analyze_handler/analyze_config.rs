@@ -0,0 +1,44 @@
+// analyze_config.rs - Encapsulates settings for the skeleton analysis
+// Copyright (C) 2019 Denis Karpovskiy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use clap::{ ArgMatches };
+use crate::cli::args;
+use crate::cli::error::{ CliError, CliResult };
+use crate::cli::stdio;
+use crate::imaging::PageRange;
+
+pub struct AnalyzeConfig<'a> {
+    pub input_file: &'a str,
+    pub skeleton_file: &'a str,
+    pub pages: PageRange
+}
+
+impl<'a> AnalyzeConfig<'a> {
+    pub fn new(matches: &'a ArgMatches) -> CliResult<Self> {
+        let input_file = matches.value_of("input-file").unwrap();
+        let skeleton_file = matches.value_of("skeleton-file").unwrap();
+        // stdin can be read only once
+        if input_file == stdio::STDIO_PATH && skeleton_file == stdio::STDIO_PATH {
+            return Err(CliError::invalid_argument("skeleton-file", "only one of the two images can be read from stdin"));
+        }
+
+        Ok(AnalyzeConfig {
+            input_file,
+            skeleton_file,
+            pages: args::parse_value(matches, "pages")?.unwrap_or_default()
+        })
+    }
+}
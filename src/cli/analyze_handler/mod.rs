@@ -0,0 +1,77 @@
+// analyze_handler/mod.rs - Handles checking the quality of skeletons
+// Copyright (C) 2019 Denis Karpovskiy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+pub mod analyze_config;
+
+use serde_json::json;
+use crate::cli::error::{ CliError, CliResult };
+use crate::cli::reporter::Reporter;
+use crate::cli::stdio;
+use crate::imaging::{ self, BinaryGrid, Document, LoadOptions };
+
+pub use analyze_config::AnalyzeConfig;
+
+pub struct AnalyzeHandler {
+
+}
+
+impl AnalyzeHandler {
+    fn load(path: &str, config: &AnalyzeConfig) -> CliResult<Document> {
+        let bytes = stdio::read_input(path)?;
+        imaging::load_document(&bytes, &config.pages, &LoadOptions::default())
+            .map_err(CliError::decoding)
+    }
+
+    pub fn execute(config: &AnalyzeConfig, reporter: &Reporter) -> CliResult<()> {
+        reporter.set_parameters(json!({ "skeleton_file": config.skeleton_file }));
+
+        reporter.report("Opening the images...");
+        let originals = reporter.time("decode", || Self::load(config.input_file, config))?;
+        let skeletons = reporter.time("decode", || Self::load(config.skeleton_file, config))?;
+        if originals.pages.len() != skeletons.pages.len() {
+            return Err(CliError::InvalidArgument(format!(
+                "The image has {} pages but the skeleton has {}",
+                originals.pages.len(), skeletons.pages.len())));
+        }
+
+        let page_count = originals.pages.len();
+        let mut text = Vec::with_capacity(page_count);
+        let mut analyses = Vec::with_capacity(page_count);
+        for (index, (original, skeleton)) in originals.pages.iter().zip(&skeletons.pages).enumerate() {
+            let original = BinaryGrid::from_image(original);
+            let skeleton = BinaryGrid::from_image(skeleton);
+            if (original.width(), original.height()) != (skeleton.width(), skeleton.height()) {
+                return Err(CliError::InvalidArgument(format!(
+                    "The image and the skeleton differ in size on page {}", index + 1)));
+            }
+
+            reporter.report("Analyzing the skeleton...");
+            reporter.set_page(Some(index));
+            let analysis = reporter.time("analysis", || imaging::analyze_skeleton(&original, &skeleton));
+            reporter.set_page(None);
+
+            if page_count > 1 {
+                text.push(format!("Page {}:\n{}", index + 1, analysis));
+            } else {
+                text.push(analysis.to_string());
+            }
+            analyses.push(analysis);
+        }
+
+        reporter.result(&text.join("\n\n"), serde_json::to_value(&analyses).unwrap());
+        Ok(())
+    }
+}
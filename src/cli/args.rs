@@ -0,0 +1,230 @@
+// args.rs - Parsing of the arguments shared by the subcommands
+// Copyright (C) 2019 Denis Karpovskiy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use clap::{ Arg, ArgMatches };
+use image::ImageFormat;
+use std::fmt::Display;
+use std::path::Path;
+use std::str::FromStr;
+use crate::imaging::{ self, LoadOptions, PageRange, Roi, VectorFormat, VectorOptions };
+use super::error::{ CliError, CliResult };
+use super::stdio;
+
+/// Parses an optional argument, rejecting values that don't parse
+pub fn parse_value<T>(matches: &ArgMatches, name: &str) -> CliResult<Option<T>>
+where
+    T: FromStr,
+    T::Err: Display
+{
+    match matches.value_of(name) {
+        Some(arg) => arg
+            .parse()
+            .map(Some)
+            .map_err(|err| CliError::invalid_argument(name, err)),
+        None => Ok(None)
+    }
+}
+
+/// Parses an argument that is required or has a default value
+pub fn parse_required<T>(matches: &ArgMatches, name: &str) -> CliResult<T>
+where
+    T: FromStr,
+    T::Err: Display
+{
+    parse_value(matches, name)?
+        .ok_or_else(|| CliError::InvalidArgument(format!("The '{}' parameter is required", name)))
+}
+
+const IMAGE_FORMATS: [&str; 7] = ["png", "jpeg", "tiff", "bmp", "gif", "pnm", "tga"];
+const IMAGE_AND_VECTOR_FORMATS: [&str; 9] = ["png", "jpeg", "tiff", "bmp", "gif", "pnm", "tga", "svg", "dxf"];
+
+/// The input and output arguments read by `IoConfig`. Vector output adds svg and dxf
+/// with their tolerance, the region adds --roi and --crop-to-roi
+pub fn io_args<'a, 'b>(vector_output: bool, region: bool) -> Vec<Arg<'a, 'b>> {
+    let mut args = vec![
+        Arg::with_name("input-file")
+            .short("i")
+            .long("input-file")
+            .help("Sets an input file, '-' reads the image from stdin")
+            .required(true)
+            .takes_value(true)
+            .value_name("FILE"),
+        Arg::with_name("output-file")
+            .short("o")
+            .long("output-file")
+            .help("Sets an output file, '-' writes the image to stdout")
+            .required(true)
+            .takes_value(true)
+            .value_name("FILE"),
+        Arg::with_name("format")
+            .short("f")
+            .long("format")
+            .help(if vector_output {
+                "The format of the output (required for stdout, overrides the file extension otherwise), \
+                svg and dxf write the skeleton as polylines"
+            } else {
+                "The format of the output (required for stdout, overrides the file extension otherwise)"
+            })
+            .required_if("output-file", "-")
+            .takes_value(true)
+            .value_name("FORMAT")
+            .possible_values(if vector_output { &IMAGE_AND_VECTOR_FORMATS[..] } else { &IMAGE_FORMATS[..] }),
+        Arg::with_name("pages")
+            .short("p")
+            .long("pages")
+            .help("Pages of a multi-page TIFF to process, e.g. 1-3,5 (all pages by default)")
+            .takes_value(true)
+            .value_name("RANGE"),
+        Arg::with_name("dpi")
+            .long("dpi")
+            .help("Overrides the resolution stored in the output file")
+            .takes_value(true)
+            .value_name("DPI"),
+        Arg::with_name("no-orientation")
+            .long("no-orientation")
+            .help("Ignores the EXIF orientation instead of rotating the image upright"),
+        Arg::with_name("keep-alpha")
+            .long("keep-alpha")
+            .help("Doesn't composite transparent pixels over the background colour")
+            .conflicts_with("background"),
+        Arg::with_name("background")
+            .long("background")
+            .help("The colour transparent pixels are composited over, e.g. white or \"#ffe0a0\"")
+            .takes_value(true)
+            .value_name("COLOR")
+            .default_value("white"),
+        Arg::with_name("profile")
+            .long("profile")
+            .help("Prints the wall time and the peak memory of every processing stage, \
+                the memory a stage allocates on top of what it starts with")
+    ];
+
+    if vector_output {
+        args.push(Arg::with_name("tolerance")
+            .long("tolerance")
+            .help("Simplifies the polylines of svg and dxf output, the maximum deviation in pixels (0 keeps every point)")
+            .takes_value(true)
+            .value_name("PIXELS")
+            .default_value("1.0"));
+    }
+    if region {
+        args.push(Arg::with_name("roi")
+            .long("roi")
+            .help("Processes only the rectangle x,y,width,height in pixels, the rest of the image stays as it is")
+            .takes_value(true)
+            .value_name("X,Y,W,H"));
+        args.push(Arg::with_name("crop-to-roi")
+            .long("crop-to-roi")
+            .help("Writes the processed region alone instead of the whole image")
+            .requires("roi"));
+    }
+    args
+}
+
+/// Parses the optional 'format' parameter of an image output
+pub fn parse_image_format(matches: &ArgMatches) -> CliResult<Option<ImageFormat>> {
+    match matches.value_of("format") {
+        Some(arg) => stdio::parse_format(arg)
+            .map(Some)
+            .map_err(|err| CliError::invalid_argument("format", err)),
+        None => Ok(None)
+    }
+}
+
+/// Input and output settings of the subcommands that transform images
+pub struct IoConfig<'a> {
+    pub input_file: &'a str,
+    pub output_file: &'a str,
+    pub output_format: Option<ImageFormat>,
+    pub pages: PageRange,
+    pub dpi: Option<f64>,
+    pub load_options: LoadOptions,
+    /// Trace the result and write polylines instead of the image
+    pub vector_format: Option<VectorFormat>,
+    pub vector_tolerance: f64,
+    /// Process only this region of every page
+    pub roi: Option<Roi>,
+    /// Keep only the processed region instead of pasting it back
+    pub crop_to_roi: bool
+}
+
+impl<'a> IoConfig<'a> {
+    /// Settings of a subcommand whose result is an image
+    pub fn new(matches: &'a ArgMatches) -> CliResult<Self> {
+        Self::parse(matches, false)
+    }
+
+    /// Settings of a subcommand whose result can be a skeleton, svg and dxf
+    /// output traces it into polylines
+    pub fn with_vector_output(matches: &'a ArgMatches) -> CliResult<Self> {
+        Self::parse(matches, true)
+    }
+
+    fn parse(matches: &'a ArgMatches, vector_output: bool) -> CliResult<Self> {
+        let input_file = matches.value_of("input-file").unwrap();
+        let output_file = matches.value_of("output-file").unwrap();
+        let vector_format = match matches.value_of("format") {
+            Some(arg) => VectorFormat::from_name(arg),
+            None => Path::new(output_file)
+                .extension()
+                .and_then(|ext| VectorFormat::from_name(&ext.to_string_lossy()))
+        };
+        if vector_format.is_some() && !vector_output {
+            return Err(CliError::InvalidArgument(
+                "Only skeletons can be written as svg or dxf polylines, choose an image format".to_string()));
+        }
+        let output_format = match vector_format {
+            Some(_) => None,
+            None => parse_image_format(matches)?
+        };
+        let vector_tolerance = match parse_value::<f64>(matches, "tolerance")? {
+            Some(tolerance) if tolerance < 0.0 =>
+                return Err(CliError::invalid_argument("tolerance", "must not be negative")),
+            Some(tolerance) => tolerance,
+            None => VectorOptions::default().tolerance
+        };
+        let pages = parse_value(matches, "pages")?.unwrap_or_default();
+        let dpi = match parse_value::<f64>(matches, "dpi")? {
+            Some(dpi) if dpi <= 0.0 => return Err(CliError::invalid_argument("dpi", "must be positive")),
+            dpi => dpi
+        };
+        let background = if matches.is_present("keep-alpha") {
+            None
+        } else {
+            Some(imaging::parse_color(matches.value_of("background").unwrap())
+                .map_err(|err| CliError::invalid_argument("background", err))?)
+        };
+        let load_options = LoadOptions {
+            apply_orientation: !matches.is_present("no-orientation"),
+            background
+        };
+
+        let roi = parse_value(matches, "roi")?;
+
+        Ok(IoConfig {
+            input_file,
+            output_file,
+            output_format,
+            pages,
+            dpi,
+            load_options,
+            vector_format,
+            vector_tolerance,
+            roi,
+            crop_to_roi: matches.is_present("crop-to-roi")
+        })
+    }
+}
@@ -0,0 +1,50 @@
+// bench_config.rs - Encapsulates settings for the benchmark
+// Copyright (C) 2019 Denis Karpovskiy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use clap::{ ArgMatches };
+use crate::cli::args;
+use crate::cli::error::{ CliError, CliResult };
+use crate::cli::stdio;
+
+pub struct BenchConfig<'a> {
+    pub input_files: Vec<&'a str>,
+    pub iterations: usize,
+    pub csv_file: Option<&'a str>,
+    pub json_file: Option<&'a str>
+}
+
+impl<'a> BenchConfig<'a> {
+    pub fn new(matches: &'a ArgMatches) -> CliResult<Self> {
+        let input_files = matches.values_of("input-file").unwrap().collect();
+        let iterations = args::parse_required(matches, "iterations")?;
+        if iterations == 0 {
+            return Err(CliError::invalid_argument("iterations", "must be positive"));
+        }
+
+        let csv_file = matches.value_of("csv");
+        let json_file = matches.value_of("json");
+        if csv_file == Some(stdio::STDIO_PATH) && json_file == Some(stdio::STDIO_PATH) {
+            return Err(CliError::invalid_argument("json", "the CSV is already written to stdout"));
+        }
+
+        Ok(BenchConfig {
+            input_files,
+            iterations,
+            csv_file,
+            json_file
+        })
+    }
+}
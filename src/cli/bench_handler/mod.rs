@@ -0,0 +1,174 @@
+// bench_handler/mod.rs - Handles benchmarking of the skeletonization algorithms
+// Copyright (C) 2019 Denis Karpovskiy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+pub mod bench_config;
+
+use improc_petrsu::{ BinaryImage, PixelColor };
+use image::DynamicImage;
+use serde::Serialize;
+use serde_json::json;
+use std::time::{ Duration, Instant };
+use crate::cli::error::{ CliError, CliResult };
+use crate::cli::reporter::Reporter;
+use crate::cli::skeletonize_handler::check_page;
+use crate::cli::stdio;
+use crate::imaging::{ self, LoadOptions, PageRange, SkeletonizerVariant };
+
+pub use bench_config::BenchConfig;
+
+#[derive(Serialize)]
+struct BenchResult {
+    image: String,
+    algorithm: &'static str,
+    // how many times the algorithm ran, the skeletonizers of the library
+    // don't report how many thinning passes they made
+    runs: usize,
+    median_ms: f64,
+    p90_ms: f64,
+    p99_ms: f64,
+    min_ms: f64,
+    max_ms: f64,
+    output_pixels: u64
+}
+
+// Nearest-rank percentile of the sorted times
+fn percentile(sorted: &[Duration], percent: f64) -> f64 {
+    let rank = (percent / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.max(1) - 1].as_secs_f64() * 1000.0
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains(',') || value.contains('"') {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn to_csv(results: &[BenchResult]) -> String {
+    let mut csv = "image,algorithm,runs,median_ms,p90_ms,p99_ms,min_ms,max_ms,output_pixels\n".to_string();
+    for result in results {
+        csv.push_str(&format!(
+            "{},{},{},{:.3},{:.3},{:.3},{:.3},{:.3},{}\n",
+            csv_field(&result.image),
+            csv_field(result.algorithm),
+            result.runs,
+            result.median_ms,
+            result.p90_ms,
+            result.p99_ms,
+            result.min_ms,
+            result.max_ms,
+            result.output_pixels));
+    }
+    csv
+}
+
+fn to_table(results: &[BenchResult]) -> String {
+    let mut table = format!(
+        "{:<24} {:<28} {:>6} {:>12} {:>12} {:>12} {:>12}",
+        "Image", "Algorithm", "Runs", "Median (ms)", "P90 (ms)", "P99 (ms)", "Pixels");
+    for result in results {
+        table.push_str(&format!(
+            "\n{:<24} {:<28} {:>6} {:>12.3} {:>12.3} {:>12.3} {:>12}",
+            result.image,
+            result.algorithm,
+            result.runs,
+            result.median_ms,
+            result.p90_ms,
+            result.p99_ms,
+            result.output_pixels));
+    }
+    table
+}
+
+pub struct BenchHandler {
+
+}
+
+impl BenchHandler {
+    pub fn execute(config: &BenchConfig, reporter: &Reporter) -> CliResult<()> {
+        reporter.set_parameters(json!({
+            "iterations": config.iterations,
+            "algorithms": SkeletonizerVariant::ALL.iter().map(|variant| variant.name()).collect::<Vec<_>>()
+        }));
+
+        let mut results = Vec::new();
+        for input_file in &config.input_files {
+            reporter.report(&format!("Opening {}...", input_file));
+            let bytes = stdio::read_input(input_file)?;
+            let document = imaging::load_document(&bytes, &PageRange::All, &LoadOptions::default())
+                .map_err(CliError::decoding)?;
+
+            let page_count = document.pages.len();
+            for (index, page) in document.pages.iter().enumerate() {
+                let image_name = if page_count > 1 {
+                    format!("{} (page {})", input_file, index + 1)
+                } else {
+                    input_file.to_string()
+                };
+                check_page(page)?;
+                let image = page.to_rgb();
+
+                for variant in SkeletonizerVariant::ALL.iter() {
+                    reporter.report(&format!("Benchmarking {} on {}...", variant.name(), image_name));
+                    let skeletonizer = variant.create();
+
+                    let mut times = Vec::with_capacity(config.iterations);
+                    let mut output = None;
+                    for _ in 0..config.iterations {
+                        let mut binary_image = BinaryImage::from_image(&image, PixelColor::White);
+
+                        let start = Instant::now();
+                        skeletonizer.process(&mut binary_image);
+                        times.push(start.elapsed());
+
+                        output = Some(binary_image);
+                    }
+                    times.sort();
+
+                    let output = DynamicImage::ImageRgb8(output.unwrap().to_rgb_image());
+                    results.push(BenchResult {
+                        image: image_name.clone(),
+                        algorithm: variant.name(),
+                        runs: config.iterations,
+                        median_ms: percentile(&times, 50.0),
+                        p90_ms: percentile(&times, 90.0),
+                        p99_ms: percentile(&times, 99.0),
+                        min_ms: times[0].as_secs_f64() * 1000.0,
+                        max_ms: times[times.len() - 1].as_secs_f64() * 1000.0,
+                        output_pixels: imaging::count_foreground(&output)
+                    });
+                }
+            }
+        }
+
+        if let Some(csv_file) = config.csv_file {
+            stdio::write_text(csv_file, &to_csv(&results))?;
+        }
+        if let Some(json_file) = config.json_file {
+            stdio::write_text(json_file, &serde_json::to_string_pretty(&results).unwrap())?;
+        }
+
+        // the results exported to stdout take the place of the table
+        let exported_to_stdout = [config.csv_file, config.json_file]
+            .iter()
+            .any(|file| *file == Some(stdio::STDIO_PATH));
+        if !exported_to_stdout {
+            reporter.result(&to_table(&results), serde_json::to_value(&results).unwrap());
+        }
+        Ok(())
+    }
+}
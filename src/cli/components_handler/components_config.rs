@@ -0,0 +1,72 @@
+// components_config.rs - Encapsulates settings for the component labelling
+// Copyright (C) 2019 Denis Karpovskiy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use clap::{ ArgMatches };
+use image::ImageFormat;
+use improc_petrsu::AdjacencyMode;
+use crate::cli::args;
+use crate::cli::error::{ CliError, CliResult };
+use crate::cli::skeletonize_handler::skeletonize_config;
+use crate::imaging::{ LoadOptions, PageRange, SkeletonizerVariant };
+
+pub struct ComponentsConfig<'a> {
+    pub input_file: &'a str,
+    /// The false colour image
+    pub output_file: Option<&'a str>,
+    pub output_format: Option<ImageFormat>,
+    /// The measurements of every component
+    pub csv_file: Option<&'a str>,
+    pub page: PageRange,
+    pub adjacency_mode: AdjacencyMode,
+    /// Skeletonizes the components to measure the skeleton length
+    pub variant: SkeletonizerVariant,
+    pub load_options: LoadOptions
+}
+
+impl<'a> ComponentsConfig<'a> {
+    pub fn new(matches: &'a ArgMatches) -> CliResult<Self> {
+        let input_file = matches.value_of("input-file").unwrap();
+        let output_file = matches.value_of("output-file");
+        let output_format = args::parse_image_format(matches)?;
+
+        let page: usize = args::parse_required(matches, "page")?;
+        if page == 0 {
+            return Err(CliError::invalid_argument("page", "pages are numbered from 1"));
+        }
+
+        let adjacency_mode = match matches.value_of("adjacency-mode") {
+            Some("Four") => AdjacencyMode::Four,
+            Some("Eight") => AdjacencyMode::Eight,
+            arg => return Err(CliError::invalid_argument(
+                "adjacency-mode", format!("unknown adjacency mode '{}'", arg.unwrap_or_default())))
+        };
+
+        // the adjacency of the components is reused by Rosenfeld
+        let algorithm = skeletonize_config::parse_algorithm(matches)?.0;
+        let variant = skeletonize_config::variant(algorithm, Some(adjacency_mode))?;
+
+        Ok(ComponentsConfig {
+            input_file,
+            output_file,
+            output_format,
+            csv_file: matches.value_of("csv"),
+            page: PageRange::Pages(vec![(page, page)]),
+            adjacency_mode,
+            variant,
+            load_options: LoadOptions::default()
+        })
+    }
+}
@@ -0,0 +1,87 @@
+// components_handler/mod.rs - Handles labelling and measuring the connected components
+// Copyright (C) 2019 Denis Karpovskiy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+pub mod components_config;
+
+use image::DynamicImage;
+use improc_petrsu::AdjacencyMode;
+use serde_json::json;
+use crate::cli::error::{ CliError, CliResult };
+use crate::cli::reporter::Reporter;
+use crate::cli::skeletonize_handler::skeletonize_page;
+use crate::cli::stdio;
+use crate::imaging::{ self, BinaryGrid, Components };
+
+pub use components_config::ComponentsConfig;
+
+pub struct ComponentsHandler {
+
+}
+
+impl ComponentsHandler {
+    pub fn execute(config: &ComponentsConfig, reporter: &Reporter) -> CliResult<()> {
+        let adjacency = match config.adjacency_mode {
+            AdjacencyMode::Four => "Four",
+            AdjacencyMode::Eight => "Eight"
+        };
+        reporter.set_parameters(json!({
+            "adjacency_mode": adjacency,
+            "algorithm": config.variant.name()
+        }));
+
+        reporter.report("Opening the image...");
+        let bytes = stdio::read_input(config.input_file)?;
+        let mut document = reporter.time("decode", || {
+            imaging::load_document(&bytes, &config.page, &config.load_options)
+        }).map_err(CliError::decoding)?;
+        let page = document.pages.remove(0);
+
+        reporter.report("Labelling the components...");
+        let grid = reporter.time("binarization", || BinaryGrid::binarize(&page));
+        let components = reporter.time("labelling", || Components::label(&grid, config.adjacency_mode));
+
+        let skeleton = skeletonize_page(&page, config.variant.create().as_ref(), reporter)?;
+        reporter.report("Measuring the components...");
+        let stats = reporter.time("measuring", || {
+            components.measure(&grid, &imaging::trace_graph(&BinaryGrid::from_image(&skeleton)))
+        });
+
+        let total_area: usize = stats.iter().map(|component| component.area).sum();
+        let largest = stats.iter().map(|component| component.area).max().unwrap_or(0);
+        reporter.result(
+            &format!(
+                "{} components, {} foreground pixels, the largest has {} pixels",
+                stats.len(), total_area, largest),
+            serde_json::to_value(&stats).unwrap());
+
+        reporter.report("Saving...");
+        if let Some(csv_file) = config.csv_file {
+            reporter.time("encode", || stdio::write_text(csv_file, &imaging::stats_to_csv(&stats)))?;
+        }
+        if let Some(output_file) = config.output_file {
+            let mut metadata = document.metadata;
+            metadata.history.push(format!(
+                "Labelled {} components with {}-adjacency", components.count(), adjacency.to_lowercase()));
+            let image = DynamicImage::ImageRgb8(components.to_false_color());
+            reporter.time("encode", || {
+                stdio::write_output(output_file, &[image], config.output_format, &metadata)
+            }).map_err(CliError::encoding)?;
+        }
+
+        reporter.report("Done.");
+        Ok(())
+    }
+}
@@ -0,0 +1,60 @@
+// contours_config.rs - Encapsulates settings for the contour extraction
+// Copyright (C) 2019 Denis Karpovskiy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use clap::{ ArgMatches };
+use std::path::Path;
+use crate::cli::args;
+use crate::cli::error::{ CliError, CliResult };
+use crate::imaging::{ ContourFormat, LoadOptions, PageRange };
+
+pub struct ContoursConfig<'a> {
+    pub input_file: &'a str,
+    pub output_file: &'a str,
+    pub page: PageRange,
+    pub format: ContourFormat,
+    pub load_options: LoadOptions
+}
+
+impl<'a> ContoursConfig<'a> {
+    pub fn new(matches: &'a ArgMatches) -> CliResult<Self> {
+        let input_file = matches.value_of("input-file").unwrap();
+        let output_file = matches.value_of("output-file").unwrap();
+
+        let page: usize = args::parse_required(matches, "page")?;
+        if page == 0 {
+            return Err(CliError::invalid_argument("page", "pages are numbered from 1"));
+        }
+
+        // the format given explicitly wins over the file extension
+        let format = match matches.value_of("contour-format") {
+            Some(name) => ContourFormat::from_name(name),
+            None => Some(Path::new(output_file)
+                .extension()
+                .and_then(|ext| ContourFormat::from_name(&ext.to_string_lossy()))
+                .unwrap_or(ContourFormat::Json))
+        };
+        let format = format.ok_or_else(|| CliError::invalid_argument(
+            "contour-format", "expected svg or json"))?;
+
+        Ok(ContoursConfig {
+            input_file,
+            output_file,
+            page: PageRange::Pages(vec![(page, page)]),
+            format,
+            load_options: LoadOptions::default()
+        })
+    }
+}
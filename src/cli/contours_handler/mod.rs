@@ -0,0 +1,57 @@
+// contours_handler/mod.rs - Handles extracting the outer and hole boundaries
+// Copyright (C) 2019 Denis Karpovskiy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+pub mod contours_config;
+
+use crate::cli::error::{ CliError, CliResult };
+use crate::cli::reporter::Reporter;
+use crate::cli::stdio;
+use crate::imaging::{ self, BinaryGrid, ContourKind };
+
+pub use contours_config::ContoursConfig;
+
+pub struct ContoursHandler {
+
+}
+
+impl ContoursHandler {
+    pub fn execute(config: &ContoursConfig, reporter: &Reporter) -> CliResult<()> {
+        reporter.report("Opening the image...");
+        let bytes = stdio::read_input(config.input_file)?;
+        let mut document = reporter.time("decode", || {
+            imaging::load_document(&bytes, &config.page, &config.load_options)
+        }).map_err(CliError::decoding)?;
+        let page = document.pages.remove(0);
+
+        reporter.report("Converting the image to binary...");
+        let grid = reporter.time("binarization", || BinaryGrid::binarize(&page));
+
+        reporter.report("Following the contours...");
+        let contours = reporter.time("tracing", || imaging::trace_contours(&grid));
+        let holes = contours.contours.iter().filter(|contour| contour.kind == ContourKind::Hole).count();
+        reporter.report(&format!(
+            "{} contours ({} outer, {} holes)",
+            contours.contours.len(),
+            contours.contours.len() - holes,
+            holes));
+
+        reporter.report("Saving...");
+        reporter.time("encode", || stdio::write_text(config.output_file, &contours.encode(config.format)))?;
+
+        reporter.report("Done.");
+        Ok(())
+    }
+}
@@ -15,30 +15,44 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 use clap::{ ArgMatches };
+use crate::cli::args::{ self, IoConfig };
+use crate::cli::error::{ CliError, CliResult };
+use crate::imaging::{ self, Channel, ThresholdMode };
 
 pub struct ConvertToBinaryConfig<'a> {
-    pub input_file: &'a str,
-    pub output_file: &'a str,
-    pub threshold: u32
+    pub io: IoConfig<'a>,
+    pub threshold: u32,
+    /// Thresholding on a channel or a colour range instead of the brightness
+    pub mode: Option<ThresholdMode>
 }
 
 impl<'a> ConvertToBinaryConfig<'a> {
-    pub fn new(matches: &'a ArgMatches)-> Self {
-        let input_file = matches.value_of("input-file").unwrap();
-        let output_file = matches.value_of("output-file").unwrap();
-        let threshold = match matches.value_of("threshold").unwrap().parse() {
-            Ok(val) => val,
-            Err(_) => {
-                eprintln!("Error parsing the value of the 'threshold' parameter");
-                eprintln!("Using the default value of 150");
-                150u32
-            }
+    pub fn new(matches: &'a ArgMatches)-> CliResult<Self> {
+        let io = IoConfig::new(matches)?;
+        let threshold = args::parse_required(matches, "threshold")?;
+        if threshold > 255 {
+            return Err(CliError::invalid_argument("threshold", "must be from 0 to 255"));
+        }
+
+        let mode = if let Some(channel) = matches.value_of("channel") {
+            let channel = Channel::from_name(channel).ok_or_else(|| CliError::invalid_argument(
+                "channel", format!("unknown channel '{}'", channel)))?;
+            Some(ThresholdMode::Channel { channel, threshold: threshold as u8, above: matches.is_present("above") })
+        } else if let Some(color) = matches.value_of("color-range") {
+            let color = imaging::parse_color(color).map_err(|err| CliError::invalid_argument("color-range", err))?;
+            let distance = match args::parse_required::<f64>(matches, "distance")? {
+                distance if distance < 0.0 => return Err(CliError::invalid_argument("distance", "must not be negative")),
+                distance => distance
+            };
+            Some(ThresholdMode::ColorRange { color, distance })
+        } else {
+            None
         };
 
-        ConvertToBinaryConfig {
-            input_file,
-            output_file,
-            threshold
-        }
+        Ok(ConvertToBinaryConfig {
+            io,
+            threshold,
+            mode
+        })
     }
 }
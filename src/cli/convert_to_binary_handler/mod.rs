@@ -20,6 +20,12 @@ use improc_petrsu::{
     BinaryImageConverter,
     ThresholdBinaryImageConverter
 };
+use image::DynamicImage;
+use serde_json::json;
+use crate::cli::error::CliResult;
+use crate::cli::reporter::Reporter;
+use crate::cli::transform;
+use crate::imaging::ThresholdMode;
 
 pub use convert_to_binary_config::ConvertToBinaryConfig;
 
@@ -28,24 +34,37 @@ pub struct ConvertToBinaryHandler {
 }
 
 impl ConvertToBinaryHandler {
-    pub fn execute(config: &ConvertToBinaryConfig) {
+    pub fn execute(config: &ConvertToBinaryConfig, reporter: &Reporter) -> CliResult<()> {
         let converter = ThresholdBinaryImageConverter::new(config.threshold);
+        let description = match config.mode {
+            Some(mode) => mode.description(),
+            None => format!("Converted to binary with threshold: {}", config.threshold)
+        };
 
-        println!("Opening the image...");
-        let mut image = image::open(config.input_file).unwrap_or_else(|err| {
-            eprintln!("Error opening image: {}", err);
-            std::process::exit(1);
-        }).to_rgb();
-
-        println!("Converting the image to binary...");
-        converter.convert_to_binary(&mut image);
-
-        println!("Saving...");
-        image.save(config.output_file).unwrap_or_else(|err| {
-            eprintln!("Failed to save the resulting image: {}", err);
-            std::process::exit(1);
+        reporter.set_parameters(match config.mode {
+            Some(ThresholdMode::Channel { channel, threshold, above }) => json!({
+                "channel": channel.name(),
+                "threshold": threshold,
+                "above": above
+            }),
+            Some(ThresholdMode::ColorRange { color, distance }) => json!({
+                "color": format!("#{:02x}{:02x}{:02x}", color[0], color[1], color[2]),
+                "distance": distance
+            }),
+            None => json!({ "threshold": config.threshold })
         });
 
-        println!("Done.");
+        transform::transform_pages(&config.io, reporter, description, |page| {
+            reporter.report("Converting the image to binary...");
+            let image = match config.mode {
+                Some(mode) => reporter.time("binarization", || mode.apply(&page)),
+                None => {
+                    let mut image = page.to_rgb();
+                    reporter.time("binarization", || converter.convert_to_binary(&mut image));
+                    image
+                }
+            };
+            Ok(DynamicImage::ImageRgb8(image))
+        })
     }
 }
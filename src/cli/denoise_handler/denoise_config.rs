@@ -0,0 +1,55 @@
+// denoise_config.rs - Encapsulates settings for the noise removal
+// Copyright (C) 2019 Denis Karpovskiy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use clap::{ ArgMatches };
+use improc_petrsu::AdjacencyMode;
+use crate::cli::args::{ self, IoConfig };
+use crate::cli::error::{ CliError, CliResult };
+
+pub struct DenoiseConfig<'a> {
+    pub io: IoConfig<'a>,
+    /// Remove the components with fewer pixels
+    pub min_area: Option<usize>,
+    /// Fill the holes with fewer pixels
+    pub max_hole_area: Option<usize>,
+    pub adjacency_mode: AdjacencyMode
+}
+
+impl<'a> DenoiseConfig<'a> {
+    pub fn new(matches: &'a ArgMatches) -> CliResult<Self> {
+        let io = IoConfig::new(matches)?;
+        let min_area = args::parse_value(matches, "min-area")?;
+        let max_hole_area = args::parse_value(matches, "max-hole-area")?;
+        if min_area.is_none() && max_hole_area.is_none() {
+            return Err(CliError::InvalidArgument(
+                "Either 'min-area' or 'max-hole-area' is required".to_string()));
+        }
+
+        let adjacency_mode = match matches.value_of("adjacency-mode") {
+            Some("Four") => AdjacencyMode::Four,
+            Some("Eight") => AdjacencyMode::Eight,
+            arg => return Err(CliError::invalid_argument(
+                "adjacency-mode", format!("unknown adjacency mode '{}'", arg.unwrap_or_default())))
+        };
+
+        Ok(DenoiseConfig {
+            io,
+            min_area,
+            max_hole_area,
+            adjacency_mode
+        })
+    }
+}
@@ -0,0 +1,78 @@
+// denoise_handler/mod.rs - Handles removing specks and filling small holes
+// Copyright (C) 2019 Denis Karpovskiy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+pub mod denoise_config;
+
+use image::DynamicImage;
+use improc_petrsu::AdjacencyMode;
+use serde_json::json;
+use crate::cli::error::CliResult;
+use crate::cli::reporter::Reporter;
+use crate::cli::transform;
+use crate::imaging::{ self, BinaryGrid };
+
+pub use denoise_config::DenoiseConfig;
+
+pub struct DenoiseHandler {
+
+}
+
+/// Binarizes the page, then removes the small components and fills the small holes
+pub fn denoise_page(
+    page: &DynamicImage,
+    min_area: Option<usize>,
+    max_hole_area: Option<usize>,
+    adjacency_mode: AdjacencyMode,
+    reporter: &Reporter
+) -> DynamicImage {
+    reporter.report("Converting the image to binary...");
+    let mut grid = reporter.time("binarization", || BinaryGrid::binarize(page));
+
+    if let Some(min_area) = min_area {
+        reporter.report("Removing small components...");
+        grid = reporter.time("component_removal", || imaging::remove_small_components(&grid, min_area, adjacency_mode));
+    }
+    if let Some(max_hole_area) = max_hole_area {
+        reporter.report("Filling small holes...");
+        grid = reporter.time("hole_filling", || imaging::fill_small_holes(&grid, max_hole_area, adjacency_mode));
+    }
+
+    DynamicImage::ImageRgb8(grid.to_rgb())
+}
+
+impl DenoiseHandler {
+    pub fn execute(config: &DenoiseConfig, reporter: &Reporter) -> CliResult<()> {
+        let mut descriptions = Vec::new();
+        if let Some(min_area) = config.min_area {
+            descriptions.push(format!("Removed components smaller than {} pixels", min_area));
+        }
+        if let Some(max_hole_area) = config.max_hole_area {
+            descriptions.push(format!("Filled holes smaller than {} pixels", max_hole_area));
+        }
+        reporter.set_parameters(json!({
+            "min_area": config.min_area,
+            "max_hole_area": config.max_hole_area,
+            "adjacency_mode": match config.adjacency_mode {
+                AdjacencyMode::Four => "Four",
+                AdjacencyMode::Eight => "Eight"
+            }
+        }));
+
+        transform::transform_pages(&config.io, reporter, descriptions.join(", "), |page| {
+            Ok(denoise_page(&page, config.min_area, config.max_hole_area, config.adjacency_mode, reporter))
+        })
+    }
+}
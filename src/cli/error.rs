@@ -0,0 +1,95 @@
+// error.rs - Errors of the CLI and their exit codes
+// Copyright (C) 2019 Denis Karpovskiy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use image::ImageError;
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+pub type CliResult<T> = Result<T, CliError>;
+
+#[derive(Debug)]
+pub enum CliError {
+    Io(io::Error),
+    Decode(ImageError),
+    Encode(ImageError),
+    InvalidArgument(String),
+    Processing(String),
+}
+
+impl CliError {
+    /// Exit codes follow sysexits.h so that scripts can tell the categories apart
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            CliError::InvalidArgument(_) => 64,
+            CliError::Decode(_) => 65,
+            CliError::Processing(_) => 70,
+            CliError::Encode(_) => 73,
+            CliError::Io(_) => 74
+        }
+    }
+
+    pub fn invalid_argument(name: &str, message: impl fmt::Display) -> Self {
+        CliError::InvalidArgument(format!("Invalid value of the '{}' parameter: {}", name, message))
+    }
+
+    /// Wraps an error that happened while reading the input image
+    pub fn decoding(err: ImageError) -> Self {
+        match err {
+            ImageError::IoError(err) => CliError::Io(err),
+            ImageError::Parameter(_) => CliError::InvalidArgument(err.to_string()),
+            err => CliError::Decode(err)
+        }
+    }
+
+    /// Wraps an error that happened while writing the resulting image
+    pub fn encoding(err: ImageError) -> Self {
+        match err {
+            ImageError::IoError(err) => CliError::Io(err),
+            ImageError::Parameter(_) | ImageError::Unsupported(_) =>
+                CliError::InvalidArgument(err.to_string()),
+            err => CliError::Encode(err)
+        }
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::Io(err) => write!(f, "I/O error: {}", err),
+            CliError::Decode(err) => write!(f, "Error opening image: {}", err),
+            CliError::Encode(err) => write!(f, "Failed to save the resulting image: {}", err),
+            CliError::InvalidArgument(message) => write!(f, "{}", message),
+            CliError::Processing(message) => write!(f, "Processing failed: {}", message)
+        }
+    }
+}
+
+impl Error for CliError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CliError::Io(err) => Some(err),
+            CliError::Decode(err) | CliError::Encode(err) => Some(err),
+            _ => None
+        }
+    }
+}
+
+impl From<io::Error> for CliError {
+    fn from(err: io::Error) -> Self {
+        CliError::Io(err)
+    }
+}
@@ -0,0 +1,68 @@
+// geometry_config.rs - Encapsulates settings for the geometric transforms
+// Copyright (C) 2019 Denis Karpovskiy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use clap::{ ArgMatches };
+use crate::cli::args::{ self, IoConfig };
+use crate::cli::error::{ CliError, CliResult };
+use crate::imaging::{ FlipDirection, GeometricTransform };
+
+pub struct GeometryConfig<'a> {
+    pub io: IoConfig<'a>,
+    /// Applied in the order the options are given on the command line
+    pub transforms: Vec<GeometricTransform>
+}
+
+fn parse_positive(matches: &ArgMatches, name: &str) -> CliResult<Option<f64>> {
+    match args::parse_value::<f64>(matches, name)? {
+        Some(value) if value <= 0.0 => Err(CliError::invalid_argument(name, "must be positive")),
+        value => Ok(value)
+    }
+}
+
+impl<'a> GeometryConfig<'a> {
+    pub fn new(matches: &'a ArgMatches) -> CliResult<Self> {
+        let io = IoConfig::new(matches)?;
+        // every transform with the position of its option
+        let mut transforms = Vec::new();
+        let index = |name: &str| matches.index_of(name).unwrap_or_default();
+
+        if let Some(degrees) = args::parse_value::<f64>(matches, "rotate")? {
+            transforms.push((index("rotate"), GeometricTransform::Rotate(degrees)));
+        }
+        if let Some(direction) = matches.value_of("flip") {
+            let direction = FlipDirection::from_name(direction).ok_or_else(|| CliError::invalid_argument(
+                "flip", format!("unknown direction '{}'", direction)))?;
+            transforms.push((index("flip"), GeometricTransform::Flip(direction)));
+        }
+        if matches.is_present("deskew") {
+            let max_angle = parse_positive(matches, "max-angle")?.unwrap();
+            if max_angle >= 45.0 {
+                return Err(CliError::invalid_argument("max-angle", "must be less than 45 degrees"));
+            }
+            transforms.push((index("deskew"), GeometricTransform::Deskew(max_angle)));
+        }
+        if let Some(dpi) = parse_positive(matches, "resample")? {
+            let source_dpi = parse_positive(matches, "source-dpi")?;
+            transforms.push((index("resample"), GeometricTransform::Resample { dpi, source_dpi }));
+        }
+        transforms.sort_by_key(|&(index, _)| index);
+
+        Ok(GeometryConfig {
+            io,
+            transforms: transforms.into_iter().map(|(_, transform)| transform).collect()
+        })
+    }
+}
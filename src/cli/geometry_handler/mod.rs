@@ -0,0 +1,73 @@
+// geometry_handler/mod.rs - Handles rotating, flipping, deskewing and resampling
+// Copyright (C) 2019 Denis Karpovskiy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+pub mod geometry_config;
+
+use image::DynamicImage;
+use serde_json::json;
+use crate::cli::error::{ CliError, CliResult };
+use crate::cli::reporter::Reporter;
+use crate::cli::transform;
+use crate::imaging::{ self, GeometricTransform, ImageMetadata };
+
+pub use geometry_config::GeometryConfig;
+
+pub struct GeometryHandler {
+
+}
+
+/// Applies the transform to the page, resampling updates the resolution in the metadata
+pub fn transform_page(
+    page: &DynamicImage,
+    transform: GeometricTransform,
+    metadata: &mut ImageMetadata,
+    reporter: &Reporter
+) -> CliResult<DynamicImage> {
+    match transform {
+        GeometricTransform::Deskew(max_angle) => {
+            reporter.report("Deskewing the image...");
+            let (result, angle) = reporter.time("deskew", || imaging::deskew(page, max_angle));
+            reporter.report(&format!("Rotated by {:.2} degrees clockwise", angle));
+            Ok(result)
+        },
+        GeometricTransform::Resample { .. } => {
+            reporter.report("Resampling the image...");
+            reporter.time("resample", || transform.apply(page, &mut metadata.dpi))
+                .map_err(CliError::InvalidArgument)
+        },
+        GeometricTransform::Rotate(_) | GeometricTransform::Flip(_) => {
+            reporter.report(match transform {
+                GeometricTransform::Rotate(_) => "Rotating the image...",
+                _ => "Flipping the image..."
+            });
+            reporter.time("geometry", || transform.apply(page, &mut metadata.dpi))
+                .map_err(CliError::Processing)
+        }
+    }
+}
+
+impl GeometryHandler {
+    pub fn execute(config: &GeometryConfig, reporter: &Reporter) -> CliResult<()> {
+        let descriptions: Vec<String> = config.transforms.iter().map(|transform| transform.description()).collect();
+        reporter.set_parameters(json!({ "transforms": descriptions }));
+
+        transform::transform_document(&config.io, reporter, descriptions.join(", then "), |page, metadata| {
+            config.transforms
+                .iter()
+                .try_fold(page, |page, &transform| transform_page(&page, transform, metadata, reporter))
+        })
+    }
+}
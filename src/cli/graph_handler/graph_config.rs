@@ -0,0 +1,71 @@
+// graph_config.rs - Encapsulates settings for the graph extraction
+// Copyright (C) 2019 Denis Karpovskiy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use clap::{ ArgMatches };
+use std::path::Path;
+use crate::cli::args;
+use crate::cli::error::{ CliError, CliResult };
+use crate::cli::skeletonize_handler::skeletonize_config;
+use crate::imaging::{ GraphFormat, LoadOptions, PageRange, SkeletonizerVariant };
+
+pub struct GraphConfig<'a> {
+    pub input_file: &'a str,
+    pub output_file: &'a str,
+    pub page: PageRange,
+    /// Skeletonize the input first, otherwise it already is a skeleton
+    pub variant: Option<SkeletonizerVariant>,
+    pub format: GraphFormat,
+    pub load_options: LoadOptions
+}
+
+impl<'a> GraphConfig<'a> {
+    pub fn new(matches: &'a ArgMatches) -> CliResult<Self> {
+        let input_file = matches.value_of("input-file").unwrap();
+        let output_file = matches.value_of("output-file").unwrap();
+
+        let page: usize = args::parse_required(matches, "page")?;
+        if page == 0 {
+            return Err(CliError::invalid_argument("page", "pages are numbered from 1"));
+        }
+
+        let variant = if matches.is_present("algorithm") {
+            let (algorithm, adjacency_mode) = skeletonize_config::parse_algorithm(matches)?;
+            Some(skeletonize_config::variant(algorithm, adjacency_mode)?)
+        } else {
+            None
+        };
+
+        // the format given explicitly wins over the file extension
+        let format = match matches.value_of("graph-format") {
+            Some(name) => GraphFormat::from_name(name),
+            None => Some(Path::new(output_file)
+                .extension()
+                .and_then(|ext| GraphFormat::from_name(&ext.to_string_lossy()))
+                .unwrap_or(GraphFormat::Json))
+        };
+        let format = format.ok_or_else(|| CliError::invalid_argument(
+            "graph-format", "expected json, graphml or dot"))?;
+
+        Ok(GraphConfig {
+            input_file,
+            output_file,
+            page: PageRange::Pages(vec![(page, page)]),
+            variant,
+            format,
+            load_options: LoadOptions::default()
+        })
+    }
+}
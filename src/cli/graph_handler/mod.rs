@@ -0,0 +1,66 @@
+// graph_handler/mod.rs - Handles extracting the skeleton graph
+// Copyright (C) 2019 Denis Karpovskiy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+pub mod graph_config;
+
+use serde_json::json;
+use crate::cli::error::{ CliError, CliResult };
+use crate::cli::reporter::Reporter;
+use crate::cli::skeletonize_handler::skeletonize_page;
+use crate::cli::stdio;
+use crate::imaging::{ self, BinaryGrid, NodeKind };
+
+pub use graph_config::GraphConfig;
+
+pub struct GraphHandler {
+
+}
+
+impl GraphHandler {
+    pub fn execute(config: &GraphConfig, reporter: &Reporter) -> CliResult<()> {
+        reporter.set_parameters(json!({
+            "algorithm": config.variant.map(|variant| variant.name())
+        }));
+
+        reporter.report("Opening the image...");
+        let bytes = stdio::read_input(config.input_file)?;
+        let mut document = reporter.time("decode", || {
+            imaging::load_document(&bytes, &config.page, &config.load_options)
+        }).map_err(CliError::decoding)?;
+        let page = document.pages.remove(0);
+
+        let skeleton = match config.variant {
+            Some(variant) => skeletonize_page(&page, variant.create().as_ref(), reporter)?,
+            None => page
+        };
+
+        reporter.report("Tracing the graph...");
+        let graph = reporter.time("tracing", || imaging::trace_graph(&BinaryGrid::from_image(&skeleton)));
+        let count_nodes = |kind| graph.nodes.iter().filter(|node| node.kind == kind).count();
+        reporter.report(&format!(
+            "{} nodes ({} endpoints, {} junctions), {} edges",
+            graph.nodes.len(),
+            count_nodes(NodeKind::Endpoint),
+            count_nodes(NodeKind::Junction),
+            graph.edges.len()));
+
+        reporter.report("Saving...");
+        reporter.time("encode", || stdio::write_text(config.output_file, &graph.encode(config.format)))?;
+
+        reporter.report("Done.");
+        Ok(())
+    }
+}
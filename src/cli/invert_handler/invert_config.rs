@@ -0,0 +1,31 @@
+// invert_config.rs - Encapsulates settings for the inversion
+// Copyright (C) 2019 Denis Karpovskiy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use clap::{ ArgMatches };
+use crate::cli::args::IoConfig;
+use crate::cli::error::CliResult;
+
+pub struct InvertConfig<'a> {
+    pub io: IoConfig<'a>
+}
+
+impl<'a> InvertConfig<'a> {
+    pub fn new(matches: &'a ArgMatches) -> CliResult<Self> {
+        Ok(InvertConfig {
+            io: IoConfig::new(matches)?
+        })
+    }
+}
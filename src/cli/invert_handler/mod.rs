@@ -0,0 +1,42 @@
+// invert_handler/mod.rs - Handles swapping the foreground and the background
+// Copyright (C) 2019 Denis Karpovskiy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+pub mod invert_config;
+
+use image::DynamicImage;
+use crate::cli::error::CliResult;
+use crate::cli::reporter::Reporter;
+use crate::cli::transform;
+use crate::imaging;
+
+pub use invert_config::InvertConfig;
+
+pub struct InvertHandler {
+
+}
+
+pub fn invert_page(page: &DynamicImage, reporter: &Reporter) -> DynamicImage {
+    reporter.report("Inverting the image...");
+    reporter.time("invert", || imaging::invert(page))
+}
+
+impl InvertHandler {
+    pub fn execute(config: &InvertConfig, reporter: &Reporter) -> CliResult<()> {
+        transform::transform_pages(&config.io, reporter, "Inverted the image".to_string(), |page| {
+            Ok(invert_page(&page, reporter))
+        })
+    }
+}
@@ -0,0 +1,82 @@
+// medial_axis_config.rs - Encapsulates settings for the medial axis
+// Copyright (C) 2019 Denis Karpovskiy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use clap::{ ArgMatches };
+use image::ImageFormat;
+use std::path::Path;
+use crate::cli::args;
+use crate::cli::error::{ CliError, CliResult };
+use crate::cli::skeletonize_handler::skeletonize_config;
+use crate::cli::stdio;
+use crate::imaging::{ LoadOptions, PageRange, SkeletonizerVariant };
+
+pub enum MedialAxisOutput {
+    /// One "x,y,radius" line per skeleton pixel
+    Csv,
+    /// 16-bit grayscale, the format is taken from the file extension when it's missing
+    Image(Option<ImageFormat>)
+}
+
+pub struct MedialAxisConfig<'a> {
+    pub input_file: &'a str,
+    pub output_file: &'a str,
+    pub page: PageRange,
+    pub variant: SkeletonizerVariant,
+    pub output: MedialAxisOutput,
+    /// The radius is multiplied by it before it's stored in the image
+    pub scale: f64,
+    pub load_options: LoadOptions
+}
+
+impl<'a> MedialAxisConfig<'a> {
+    pub fn new(matches: &'a ArgMatches) -> CliResult<Self> {
+        let input_file = matches.value_of("input-file").unwrap();
+        let output_file = matches.value_of("output-file").unwrap();
+
+        let page: usize = args::parse_required(matches, "page")?;
+        if page == 0 {
+            return Err(CliError::invalid_argument("page", "pages are numbered from 1"));
+        }
+
+        let (algorithm, adjacency_mode) = skeletonize_config::parse_algorithm(matches)?;
+        let variant = skeletonize_config::variant(algorithm, adjacency_mode)?;
+
+        let format = matches.value_of("format").map(str::to_string).or_else(|| Path::new(output_file)
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase()));
+        let output = match format.as_deref() {
+            Some("csv") => MedialAxisOutput::Csv,
+            Some(name) if matches.is_present("format") => MedialAxisOutput::Image(Some(
+                stdio::parse_format(name).map_err(|err| CliError::invalid_argument("format", err))?)),
+            _ => MedialAxisOutput::Image(None)
+        };
+
+        let scale: f64 = args::parse_required(matches, "scale")?;
+        if scale <= 0.0 {
+            return Err(CliError::invalid_argument("scale", "must be positive"));
+        }
+
+        Ok(MedialAxisConfig {
+            input_file,
+            output_file,
+            page: PageRange::Pages(vec![(page, page)]),
+            variant,
+            output,
+            scale,
+            load_options: LoadOptions::default()
+        })
+    }
+}
@@ -0,0 +1,76 @@
+// medial_axis_handler/mod.rs - Handles the skeleton annotated with the stroke radius
+// Copyright (C) 2019 Denis Karpovskiy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+pub mod medial_axis_config;
+
+use image::DynamicImage;
+use serde_json::json;
+use crate::cli::error::{ CliError, CliResult };
+use crate::cli::reporter::Reporter;
+use crate::cli::skeletonize_handler::skeletonize_page;
+use crate::cli::stdio;
+use crate::imaging::{ self, BinaryGrid };
+
+pub use medial_axis_config::{ MedialAxisConfig, MedialAxisOutput };
+
+pub struct MedialAxisHandler {
+
+}
+
+impl MedialAxisHandler {
+    pub fn execute(config: &MedialAxisConfig, reporter: &Reporter) -> CliResult<()> {
+        reporter.set_parameters(json!({
+            "algorithm": config.variant.name(),
+            "scale": config.scale
+        }));
+
+        reporter.report("Opening the image...");
+        let bytes = stdio::read_input(config.input_file)?;
+        let mut document = reporter.time("decode", || {
+            imaging::load_document(&bytes, &config.page, &config.load_options)
+        }).map_err(CliError::decoding)?;
+        let page = document.pages.remove(0);
+
+        let shape = BinaryGrid::binarize(&page);
+        let skeleton = skeletonize_page(&page, config.variant.create().as_ref(), reporter)?;
+
+        reporter.report("Computing the distance transform...");
+        let axis = reporter.time("distance_transform", || {
+            imaging::medial_axis(&shape, &BinaryGrid::from_image(&skeleton))
+        });
+        reporter.result(
+            &format!("{} skeleton pixels, maximum radius {:.2} pixels", axis.points.len(), axis.max_radius()),
+            json!({ "points": axis.points.len(), "max_radius": axis.max_radius() }));
+
+        reporter.report("Saving...");
+        match config.output {
+            MedialAxisOutput::Csv => reporter.time("encode", || stdio::write_text(config.output_file, &axis.to_csv()))?,
+            MedialAxisOutput::Image(format) => {
+                let mut metadata = document.metadata;
+                metadata.history.push(format!(
+                    "Medial axis of the {} skeleton, radius in 1/{} pixels",
+                    config.variant.name(), config.scale));
+                let image = DynamicImage::ImageLuma16(axis.to_gray16(config.scale));
+                reporter.time("encode", || {
+                    stdio::write_output(config.output_file, &[image], format, &metadata)
+                }).map_err(CliError::encoding)?
+            }
+        }
+
+        reporter.report("Done.");
+        Ok(())
+    }
+}
@@ -15,21 +15,118 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 mod skeletonize_handler;
+mod bench_handler;
+mod analyze_handler;
+mod graph_handler;
+mod pipeline_handler;
+mod morphology_handler;
+mod denoise_handler;
+mod medial_axis_handler;
+mod components_handler;
+mod contours_handler;
+mod geometry_handler;
+mod invert_handler;
 mod convert_to_binary_handler;
+mod args;
+mod error;
+mod reporter;
+mod stdio;
+mod transform;
 
 use skeletonize_handler::{ SkeletonizeHandler, SkeletonizeConfig };
 use convert_to_binary_handler::{ ConvertToBinaryConfig, ConvertToBinaryHandler };
-use clap::{ load_yaml, App };
+use bench_handler::{ BenchConfig, BenchHandler };
+use analyze_handler::{ AnalyzeConfig, AnalyzeHandler };
+use graph_handler::{ GraphConfig, GraphHandler };
+use pipeline_handler::{ PipelineConfig, PipelineHandler };
+use morphology_handler::{ MorphologyConfig, MorphologyHandler };
+use denoise_handler::{ DenoiseConfig, DenoiseHandler };
+use medial_axis_handler::{ MedialAxisConfig, MedialAxisHandler };
+use components_handler::{ ComponentsConfig, ComponentsHandler };
+use contours_handler::{ ContoursConfig, ContoursHandler };
+use geometry_handler::{ GeometryConfig, GeometryHandler };
+use invert_handler::{ InvertConfig, InvertHandler };
+use clap::{ load_yaml, App, ErrorKind, SubCommand };
+use reporter::{ OutputFormat, Reporter };
 
-pub fn run_ui() {
+pub use error::{ CliError, CliResult };
+
+pub fn run_ui() -> CliResult<()> {
     let yaml = load_yaml!("cli.yml");
-    let args = App::from_yaml(yaml).get_matches();
-
-    if let Some(matches) = args.subcommand_matches("skeletonize") {
-        let config = SkeletonizeConfig::new(matches);
-        SkeletonizeHandler::execute(&config);
-    } else if let Some(matches) = args.subcommand_matches("convert-to-binary") {
-        let config = ConvertToBinaryConfig::new(matches);
-        ConvertToBinaryHandler::execute(&config);
-    }
+    let app = yaml["image_subcommands"]
+        .as_vec()
+        .unwrap()
+        .iter()
+        .fold(App::from_yaml(yaml), |app, subcommand_yaml| {
+            let subcommand = SubCommand::from_yaml(subcommand_yaml);
+            // only skeletons are traced into polylines, rotation and resampling change the page size
+            let vector_output = matches!(subcommand.get_name(), "skeletonize" | "pipeline");
+            let region = subcommand.get_name() != "geometry";
+            app.subcommand(subcommand.args(&args::io_args(vector_output, region)))
+        });
+    let args = match app.get_matches_safe() {
+        Ok(args) => args,
+        Err(err) => match err.kind {
+            ErrorKind::HelpDisplayed | ErrorKind::VersionDisplayed => err.exit(),
+            _ => return Err(CliError::InvalidArgument(err.message))
+        }
+    };
+
+    let (operation, matches) = match args.subcommand() {
+        (operation, Some(matches)) => (operation, matches),
+        _ => return Ok(())
+    };
+
+    // Global arguments end up in the matches of the subcommand when they
+    // follow its name and in the top-level ones otherwise
+    let output_format = match matches.value_of("output-format").or_else(|| args.value_of("output-format")) {
+        Some("json") => OutputFormat::Json,
+        _ => OutputFormat::Text
+    };
+    let quiet = matches.is_present("quiet") || args.is_present("quiet");
+    // the messages mustn't mix with an output streamed to stdout
+    let to_stderr = ["output-file", "csv", "json"]
+        .iter()
+        .any(|name| matches.value_of(name) == Some(stdio::STDIO_PATH));
+    let reporter = Reporter::new(
+        output_format,
+        quiet,
+        matches.is_present("profile"),
+        to_stderr,
+        operation,
+        &matches.values_of("input-file").map_or(Vec::new(), |files| files.collect::<Vec<_>>()).join(", "),
+        matches.value_of("output-file").unwrap_or_default());
+
+    let result = match operation {
+        "skeletonize" => SkeletonizeConfig::new(matches)
+            .and_then(|config| SkeletonizeHandler::execute(&config, &reporter)),
+        "convert-to-binary" => ConvertToBinaryConfig::new(matches)
+            .and_then(|config| ConvertToBinaryHandler::execute(&config, &reporter)),
+        "bench" => BenchConfig::new(matches)
+            .and_then(|config| BenchHandler::execute(&config, &reporter)),
+        "analyze" => AnalyzeConfig::new(matches)
+            .and_then(|config| AnalyzeHandler::execute(&config, &reporter)),
+        "graph" => GraphConfig::new(matches)
+            .and_then(|config| GraphHandler::execute(&config, &reporter)),
+        "pipeline" => PipelineConfig::new(matches)
+            .and_then(|config| PipelineHandler::execute(&config, &reporter)),
+        "morphology" => MorphologyConfig::new(matches)
+            .and_then(|config| MorphologyHandler::execute(&config, &reporter)),
+        "denoise" => DenoiseConfig::new(matches)
+            .and_then(|config| DenoiseHandler::execute(&config, &reporter)),
+        "medial-axis" => MedialAxisConfig::new(matches)
+            .and_then(|config| MedialAxisHandler::execute(&config, &reporter)),
+        "components" => ComponentsConfig::new(matches)
+            .and_then(|config| ComponentsHandler::execute(&config, &reporter)),
+        "contours" => ContoursConfig::new(matches)
+            .and_then(|config| ContoursHandler::execute(&config, &reporter)),
+        "geometry" => GeometryConfig::new(matches)
+            .and_then(|config| GeometryHandler::execute(&config, &reporter)),
+        "invert" => InvertConfig::new(matches)
+            .and_then(|config| InvertHandler::execute(&config, &reporter)),
+        _ => Ok(())
+    };
+
+    reporter.finish(&result);
+    result
 }
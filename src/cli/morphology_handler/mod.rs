@@ -0,0 +1,62 @@
+// morphology_handler/mod.rs - Handles erosion, dilation, opening and closing
+// Copyright (C) 2019 Denis Karpovskiy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+pub mod morphology_config;
+
+use image::DynamicImage;
+use serde_json::json;
+use crate::cli::error::CliResult;
+use crate::cli::reporter::Reporter;
+use crate::cli::transform;
+use crate::imaging::{ self, BinaryGrid, MorphologyOperation, StructuringElement };
+
+pub use morphology_config::MorphologyConfig;
+
+pub struct MorphologyHandler {
+
+}
+
+/// Binarizes the page and applies the operation to it
+pub fn morphology_page(
+    page: &DynamicImage,
+    operation: MorphologyOperation,
+    element: &StructuringElement,
+    reporter: &Reporter
+) -> DynamicImage {
+    reporter.report("Converting the image to binary...");
+    let grid = reporter.time("binarization", || BinaryGrid::binarize(page));
+
+    reporter.report(&format!("Applying {}...", operation.name()));
+    let result = reporter.time("morphology", || imaging::morphology(&grid, operation, element));
+    DynamicImage::ImageRgb8(result.to_rgb())
+}
+
+impl MorphologyHandler {
+    pub fn execute(config: &MorphologyConfig, reporter: &Reporter) -> CliResult<()> {
+        let description = format!(
+            "Applied {} with a {} of radius {}",
+            config.operation.name(), config.element.shape.name(), config.element.radius);
+        reporter.set_parameters(json!({
+            "operation": config.operation.name(),
+            "shape": config.element.shape.name(),
+            "size": config.element.radius
+        }));
+
+        transform::transform_pages(&config.io, reporter, description, |page| {
+            Ok(morphology_page(&page, config.operation, &config.element, reporter))
+        })
+    }
+}
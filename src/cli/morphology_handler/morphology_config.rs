@@ -0,0 +1,45 @@
+// morphology_config.rs - Encapsulates settings for the morphology operations
+// Copyright (C) 2019 Denis Karpovskiy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use clap::{ ArgMatches };
+use crate::cli::args::{ self, IoConfig };
+use crate::cli::error::{ CliError, CliResult };
+use crate::imaging::{ ElementShape, MorphologyOperation, StructuringElement };
+
+pub struct MorphologyConfig<'a> {
+    pub io: IoConfig<'a>,
+    pub operation: MorphologyOperation,
+    pub element: StructuringElement
+}
+
+impl<'a> MorphologyConfig<'a> {
+    pub fn new(matches: &'a ArgMatches) -> CliResult<Self> {
+        let io = IoConfig::new(matches)?;
+        let operation = matches.value_of("operation").unwrap();
+        let operation = MorphologyOperation::from_name(operation).ok_or_else(|| CliError::invalid_argument(
+            "operation", format!("unknown operation '{}'", operation)))?;
+        let shape = matches.value_of("shape").unwrap();
+        let shape = ElementShape::from_name(shape).ok_or_else(|| CliError::invalid_argument(
+            "shape", format!("unknown shape '{}'", shape)))?;
+        let radius = args::parse_required(matches, "size")?;
+
+        Ok(MorphologyConfig {
+            io,
+            operation,
+            element: StructuringElement { shape, radius }
+        })
+    }
+}
@@ -0,0 +1,80 @@
+// pipeline_handler/mod.rs - Handles running several operations in a row
+// Copyright (C) 2019 Denis Karpovskiy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+pub mod pipeline_config;
+
+use improc_petrsu::{ AdjacencyMode, BinaryImageConverter, ThresholdBinaryImageConverter };
+use image::DynamicImage;
+use serde_json::json;
+use crate::cli::error::CliResult;
+use crate::cli::reporter::Reporter;
+use crate::cli::denoise_handler::denoise_page;
+use crate::cli::geometry_handler::transform_page;
+use crate::cli::invert_handler::invert_page;
+use crate::cli::morphology_handler::morphology_page;
+use crate::cli::skeletonize_handler::{ prune_page, skeletonize_page };
+use crate::cli::transform;
+use crate::imaging::ImageMetadata;
+
+pub use pipeline_config::{ PipelineConfig, PipelineStep };
+
+pub struct PipelineHandler {
+
+}
+
+impl PipelineHandler {
+    fn apply(
+        step: PipelineStep,
+        page: DynamicImage,
+        metadata: &mut ImageMetadata,
+        reporter: &Reporter
+    ) -> CliResult<DynamicImage> {
+        match step {
+            PipelineStep::Binarize(threshold) => {
+                let mut image = page.to_rgb();
+                reporter.report("Converting the image to binary...");
+                reporter.time("binarization", || {
+                    ThresholdBinaryImageConverter::new(threshold).convert_to_binary(&mut image)
+                });
+                Ok(DynamicImage::ImageRgb8(image))
+            },
+            PipelineStep::Skeletonize(variant) => skeletonize_page(&page, variant.create().as_ref(), reporter),
+            PipelineStep::Prune(min_length) => Ok(prune_page(&page, min_length, reporter)),
+            PipelineStep::Morphology(operation, element) => Ok(morphology_page(&page, operation, &element, reporter)),
+            PipelineStep::RemoveSmall(min_area) =>
+                Ok(denoise_page(&page, Some(min_area), None, AdjacencyMode::Eight, reporter)),
+            PipelineStep::FillHoles(max_area) =>
+                Ok(denoise_page(&page, None, Some(max_area), AdjacencyMode::Eight, reporter)),
+            PipelineStep::Geometry(transform) => transform_page(&page, transform, metadata, reporter),
+            PipelineStep::Preprocess(filter) => {
+                reporter.report("Preprocessing the image...");
+                Ok(reporter.time("preprocessing", || filter.apply(&page)))
+            },
+            PipelineStep::Invert => Ok(invert_page(&page, reporter))
+        }
+    }
+
+    pub fn execute(config: &PipelineConfig, reporter: &Reporter) -> CliResult<()> {
+        let descriptions: Vec<String> = config.steps.iter().map(|step| step.description()).collect();
+        reporter.set_parameters(json!({ "steps": descriptions }));
+
+        transform::transform_document(&config.io, reporter, descriptions.join(", then "), |page, metadata| {
+            config.steps
+                .iter()
+                .try_fold(page, |page, &step| Self::apply(step, page, metadata, reporter))
+        })
+    }
+}
@@ -0,0 +1,214 @@
+// pipeline_config.rs - Encapsulates the steps of a processing pipeline
+// Copyright (C) 2019 Denis Karpovskiy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use clap::{ ArgMatches };
+use std::str::FromStr;
+use crate::cli::args::IoConfig;
+use crate::cli::error::{ CliError, CliResult };
+use crate::imaging::{
+    ChannelWeights, ElementShape, FlipDirection, GeometricTransform, MorphologyOperation, PreprocessFilter,
+    SkeletonizerVariant, StructuringElement
+};
+
+/// A single operation of the pipeline, written as "name:argument:argument"
+#[derive(Clone, Copy, Debug)]
+pub enum PipelineStep {
+    Binarize(u32),
+    Skeletonize(SkeletonizerVariant),
+    Prune(usize),
+    Morphology(MorphologyOperation, StructuringElement),
+    RemoveSmall(usize),
+    FillHoles(usize),
+    Geometry(GeometricTransform),
+    Preprocess(PreprocessFilter),
+    Invert
+}
+
+// The default of deskew without an argument, in degrees
+const DEFAULT_MAX_SKEW: f64 = 10.0;
+// The default of stretch without an argument, in percent
+const DEFAULT_CONTRAST_CLIP: f64 = 1.0;
+
+fn parse_argument<T: FromStr>(step: &str, argument: Option<&str>) -> Result<T, String> {
+    let argument = argument.ok_or_else(|| format!("The '{}' step requires an argument", step))?;
+    argument
+        .parse()
+        .map_err(|_| format!("Invalid argument of the '{}' step: '{}'", step, argument))
+}
+
+impl FromStr for PipelineStep {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.trim().split(':');
+        let name = parts.next().unwrap_or_default();
+        let mut argument = || parts.next();
+
+        let step = match name {
+            "binarize" => {
+                let threshold = parse_argument(name, argument())?;
+                if threshold > 255 {
+                    return Err("The threshold must be from 0 to 255".to_string());
+                }
+                PipelineStep::Binarize(threshold)
+            },
+            "skeletonize" => PipelineStep::Skeletonize(match (argument(), argument()) {
+                (Some("Eberly"), None) => SkeletonizerVariant::Eberly,
+                (Some("ZhangSuen"), None) => SkeletonizerVariant::ZhangSuen,
+                (Some("Rosenfeld"), Some("Four")) => SkeletonizerVariant::RosenfeldFour,
+                (Some("Rosenfeld"), Some("Eight")) => SkeletonizerVariant::RosenfeldEight,
+                _ => return Err(
+                    "Expected skeletonize:Eberly, skeletonize:ZhangSuen or skeletonize:Rosenfeld:Four|Eight"
+                        .to_string())
+            }),
+            "prune" => PipelineStep::Prune(parse_argument(name, argument())?),
+            "erode" | "dilate" | "open" | "close" => {
+                let operation = MorphologyOperation::from_name(name).unwrap();
+                let radius = parse_argument(name, argument())?;
+                let shape = match argument() {
+                    Some(shape) => ElementShape::from_name(shape).ok_or_else(|| format!(
+                        "Unknown shape of the '{}' step: '{}', expected square, cross or disk", name, shape))?,
+                    None => ElementShape::Square
+                };
+                PipelineStep::Morphology(operation, StructuringElement { shape, radius })
+            },
+            "remove-small" => PipelineStep::RemoveSmall(parse_argument(name, argument())?),
+            "fill-holes" => PipelineStep::FillHoles(parse_argument(name, argument())?),
+            "rotate" => PipelineStep::Geometry(GeometricTransform::Rotate(parse_argument(name, argument())?)),
+            "flip" => {
+                let direction = argument().and_then(FlipDirection::from_name).ok_or_else(|| {
+                    "Expected flip:horizontal or flip:vertical".to_string()
+                })?;
+                PipelineStep::Geometry(GeometricTransform::Flip(direction))
+            },
+            "deskew" => {
+                let max_angle = match argument() {
+                    Some(max_angle) => parse_argument(name, Some(max_angle))?,
+                    None => DEFAULT_MAX_SKEW
+                };
+                if max_angle <= 0.0 || max_angle >= 45.0 {
+                    return Err("The angle of the 'deskew' step must be from 0 to 45 degrees".to_string());
+                }
+                PipelineStep::Geometry(GeometricTransform::Deskew(max_angle))
+            },
+            "resample" => {
+                let dpi: f64 = parse_argument(name, argument())?;
+                let source_dpi: Option<f64> = match argument() {
+                    Some(source_dpi) => Some(parse_argument(name, Some(source_dpi))?),
+                    None => None
+                };
+                if dpi <= 0.0 || matches!(source_dpi, Some(source_dpi) if source_dpi <= 0.0) {
+                    return Err("The resolutions of the 'resample' step must be positive".to_string());
+                }
+                PipelineStep::Geometry(GeometricTransform::Resample { dpi, source_dpi })
+            },
+            "grayscale" => {
+                let weights = match (argument(), argument(), argument()) {
+                    (None, _, _) => ChannelWeights::LUMA,
+                    (Some(weights), None, _) => ChannelWeights::from_name(weights).ok_or_else(|| format!(
+                        "Unknown channel weights '{}', expected luma, average, red, green, blue or R:G:B", weights))?,
+                    (red, green, blue) => ChannelWeights::new(
+                        parse_argument(name, red)?, parse_argument(name, green)?, parse_argument(name, blue)?)?
+                };
+                PipelineStep::Preprocess(PreprocessFilter::Grayscale(weights))
+            },
+            "blur" => {
+                let sigma: f32 = parse_argument(name, argument())?;
+                if sigma <= 0.0 {
+                    return Err("The sigma of the 'blur' step must be positive".to_string());
+                }
+                PipelineStep::Preprocess(PreprocessFilter::GaussianBlur(sigma))
+            },
+            "median" => PipelineStep::Preprocess(PreprocessFilter::MedianBlur(parse_argument(name, argument())?)),
+            "stretch" => {
+                let clip = match argument() {
+                    Some(clip) => parse_argument(name, Some(clip))?,
+                    None => DEFAULT_CONTRAST_CLIP
+                };
+                if !(0.0..50.0).contains(&clip) {
+                    return Err("The clipped percent of the 'stretch' step must be from 0 to 50".to_string());
+                }
+                PipelineStep::Preprocess(PreprocessFilter::StretchContrast(clip))
+            },
+            "gamma" => {
+                let gamma: f64 = parse_argument(name, argument())?;
+                if gamma <= 0.0 {
+                    return Err("The 'gamma' step requires a positive value".to_string());
+                }
+                PipelineStep::Preprocess(PreprocessFilter::Gamma(gamma))
+            },
+            "equalize" => PipelineStep::Preprocess(PreprocessFilter::EqualizeHistogram),
+            "invert" => PipelineStep::Invert,
+            "subtract-background" =>
+                PipelineStep::Preprocess(PreprocessFilter::SubtractBackground(parse_argument(name, argument())?)),
+            _ => return Err(format!("Unknown pipeline step: '{}'", name))
+        };
+
+        if parts.next().is_some() {
+            return Err(format!("Too many arguments of the '{}' step", name));
+        }
+
+        Ok(step)
+    }
+}
+
+impl PipelineStep {
+    /// The entry of the processing history
+    pub fn description(&self) -> String {
+        match self {
+            PipelineStep::Binarize(threshold) => format!("Converted to binary with threshold: {}", threshold),
+            PipelineStep::Skeletonize(variant) =>
+                format!("Skeletonized the image with the {} algorithm", variant.name()),
+            PipelineStep::Prune(min_length) => format!("Pruned spurs shorter than {} pixels", min_length),
+            PipelineStep::Morphology(operation, element) => format!(
+                "Applied {} with a {} of radius {}", operation.name(), element.shape.name(), element.radius),
+            PipelineStep::RemoveSmall(min_area) => format!("Removed components smaller than {} pixels", min_area),
+            PipelineStep::FillHoles(max_area) => format!("Filled holes smaller than {} pixels", max_area),
+            PipelineStep::Geometry(transform) => transform.description(),
+            PipelineStep::Preprocess(filter) => filter.description(),
+            PipelineStep::Invert => "Inverted the image".to_string()
+        }
+    }
+}
+
+pub struct PipelineConfig<'a> {
+    pub io: IoConfig<'a>,
+    pub steps: Vec<PipelineStep>
+}
+
+impl<'a> PipelineConfig<'a> {
+    pub fn new(matches: &'a ArgMatches) -> CliResult<Self> {
+        let io = IoConfig::with_vector_output(matches)?;
+        let steps = matches
+            .values_of("steps")
+            .unwrap()
+            .map(|step| step.parse().map_err(|err| CliError::invalid_argument("steps", err)))
+            .collect::<CliResult<Vec<PipelineStep>>>()?;
+
+        // the transformed region doesn't fit back into its place
+        let geometry = steps.iter().any(|step| matches!(step, PipelineStep::Geometry(_)));
+        if geometry && io.roi.is_some() && !io.crop_to_roi {
+            return Err(CliError::invalid_argument(
+                "steps",
+                "the geometric steps rotate, flip, deskew and resample don't fit back into the region, combine them with --roi only together with --crop-to-roi"));
+        }
+
+        Ok(PipelineConfig {
+            io,
+            steps
+        })
+    }
+}
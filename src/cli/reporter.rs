@@ -0,0 +1,196 @@
+// reporter.rs - Prints the progress of the handlers
+// Copyright (C) 2019 Denis Karpovskiy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use log::info;
+use serde::Serialize;
+use serde_json::Value;
+use std::cell::{ Cell, RefCell };
+use crate::profiler;
+use super::error::CliResult;
+
+/// How the progress and the result of a subcommand are printed
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OutputFormat {
+    /// Human-readable progress messages
+    Text,
+    /// A single JSON record per processed file, progress goes to the log
+    Json
+}
+
+#[derive(Serialize)]
+struct PageRecord {
+    page: usize,
+    width: u32,
+    height: u32,
+    foreground_before: u64,
+    foreground_after: u64
+}
+
+#[derive(Serialize)]
+struct StageTiming {
+    stage: &'static str,
+    page: Option<usize>,
+    milliseconds: f64,
+    peak_memory_bytes: usize
+}
+
+#[derive(Serialize)]
+struct Record {
+    input: String,
+    output: String,
+    operation: String,
+    parameters: Value,
+    pages: Vec<PageRecord>,
+    timings: Vec<StageTiming>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    results: Option<Value>,
+    error: Option<String>
+}
+
+/// Prints progress messages, to stderr when stdout carries an output of the
+/// subcommand, and collects the record printed in the JSON mode
+pub struct Reporter {
+    format: OutputFormat,
+    quiet: bool,
+    profile: bool,
+    to_stderr: bool,
+    page: Cell<Option<usize>>,
+    record: RefCell<Record>
+}
+
+impl Reporter {
+    pub fn new(
+        format: OutputFormat,
+        quiet: bool,
+        profile: bool,
+        to_stderr: bool,
+        operation: &str,
+        input_file: &str,
+        output_file: &str
+    ) -> Self {
+        Reporter {
+            format,
+            quiet,
+            profile,
+            to_stderr,
+            page: Cell::new(None),
+            record: RefCell::new(Record {
+                input: input_file.to_string(),
+                output: output_file.to_string(),
+                operation: operation.to_string(),
+                parameters: Value::Null,
+                pages: Vec::new(),
+                timings: Vec::new(),
+                results: None,
+                error: None
+            })
+        }
+    }
+
+    pub fn report(&self, message: &str) {
+        match self.format {
+            OutputFormat::Json => info!("{}", message),
+            OutputFormat::Text if self.quiet => {},
+            OutputFormat::Text => self.print(message)
+        }
+    }
+
+    pub fn set_parameters(&self, parameters: Value) {
+        self.record.borrow_mut().parameters = parameters;
+    }
+
+    /// Sets the 0-based index of the page the following stages belong to
+    pub fn set_page(&self, page: Option<usize>) {
+        self.page.set(page);
+    }
+
+    /// Runs the stage and records how long it took and how much memory it used
+    pub fn time<T, F: FnOnce() -> T>(&self, stage: &'static str, f: F) -> T {
+        let (result, profile) = profiler::profile(stage, f);
+
+        info!("{}", profile);
+        self.record.borrow_mut().timings.push(StageTiming {
+            stage,
+            page: self.page.get(),
+            milliseconds: profile.milliseconds(),
+            peak_memory_bytes: profile.peak_memory
+        });
+
+        result
+    }
+
+    pub fn add_page(&self, width: u32, height: u32, foreground_before: u64, foreground_after: u64) {
+        let mut record = self.record.borrow_mut();
+        let page = record.pages.len();
+        record.pages.push(PageRecord {
+            page,
+            width,
+            height,
+            foreground_before,
+            foreground_after
+        });
+    }
+
+    /// Prints the results of a subcommand that doesn't produce an image,
+    /// or stores them in the JSON record
+    pub fn result(&self, text: &str, results: Value) {
+        match self.format {
+            OutputFormat::Json => self.record.borrow_mut().results = Some(results),
+            OutputFormat::Text if self.quiet => {},
+            OutputFormat::Text => self.print(text)
+        }
+    }
+
+    fn print(&self, text: &str) {
+        if self.to_stderr {
+            eprintln!("{}", text);
+        } else {
+            println!("{}", text);
+        }
+    }
+
+    fn print_profile(&self) {
+        let record = self.record.borrow();
+        let mut table = format!("{:<16} {:>5} {:>12} {:>18}", "Stage", "Page", "Time (ms)", "Peak memory (MiB)");
+        for timing in &record.timings {
+            let page = timing.page.map_or("-".to_string(), |page| (page + 1).to_string());
+            table.push_str(&format!(
+                "\n{:<16} {:>5} {:>12.3} {:>18.1}",
+                timing.stage,
+                page,
+                timing.milliseconds,
+                timing.peak_memory_bytes as f64 / (1024.0 * 1024.0)));
+        }
+        self.print(&table);
+    }
+
+    /// Prints the JSON record with the outcome of the subcommand, or the
+    /// profiling report if it was requested
+    pub fn finish(&self, result: &CliResult<()>) {
+        if self.format != OutputFormat::Json {
+            if self.profile {
+                self.print_profile();
+            }
+            return;
+        }
+
+        let mut record = self.record.borrow_mut();
+        record.error = result.as_ref().err().map(|err| err.to_string());
+
+        let json = serde_json::to_string(&*record).unwrap();
+        self.print(&json);
+    }
+}
@@ -18,12 +18,16 @@ pub mod skeletonize_config;
 
 use improc_petrsu::{
     Skeletonizer,
-    ZhangSuenSkeletonizer,
-    EberlySkeletonizer,
-    RosenfeldSkeletonizer,
     BinaryImage,
-    PixelColor
+    PixelColor,
+    AdjacencyMode
 };
+use image::{ DynamicImage, GenericImageView };
+use serde_json::json;
+use crate::cli::error::{ CliError, CliResult };
+use crate::cli::reporter::Reporter;
+use crate::cli::transform;
+use crate::imaging::{ self, BinaryGrid };
 
 pub use skeletonize_config::SkeletonizeConfig;
 pub use skeletonize_config::SkeletonizationAlgorithm;
@@ -33,35 +37,78 @@ pub struct SkeletonizeHandler {
 }
 
 impl SkeletonizeHandler {
-    pub fn execute(config: &SkeletonizeConfig) {
+    pub fn execute(config: &SkeletonizeConfig, reporter: &Reporter) -> CliResult<()> {
 
-        let skeletonizer: Box<dyn Skeletonizer> = match config.algorithm {
-            SkeletonizationAlgorithm::ZhangSuen => Box::new(ZhangSuenSkeletonizer::new()),
-            SkeletonizationAlgorithm::Eberly => Box::new(EberlySkeletonizer::new()),
-            SkeletonizationAlgorithm::Rosenfeld => Box::new(RosenfeldSkeletonizer::new(config.adjacency_mode.unwrap()))
+        let skeletonizer = skeletonize_config::variant(config.algorithm, config.adjacency_mode)?.create();
+
+        let adjacency = match config.adjacency_mode {
+            Some(AdjacencyMode::Four) => " (four-adjacency)",
+            Some(AdjacencyMode::Eight) => " (eight-adjacency)",
+            None => ""
         };
+        let mut description = format!(
+            "Skeletonized the image with the {} algorithm{}", config.algorithm.name(), adjacency);
+        if let Some(min_length) = config.prune {
+            description.push_str(&format!(" and pruned spurs shorter than {} pixels", min_length));
+        }
 
-        println!("Opening the image...");
-        let image = image::open(config.input_file).unwrap_or_else(|err| {
-            eprintln!("Error opening image: {}", err);
-            std::process::exit(1);
-        }).to_rgb();
+        reporter.set_parameters(json!({
+            "algorithm": config.algorithm.name(),
+            "adjacency_mode": match config.adjacency_mode {
+                Some(AdjacencyMode::Four) => Some("Four"),
+                Some(AdjacencyMode::Eight) => Some("Eight"),
+                None => None
+            },
+            "prune": config.prune
+        }));
 
-        println!("Converting the image to binary...");
-        let mut binary_image = BinaryImage::from_image(&image, PixelColor::White);
+        transform::transform_pages(&config.io, reporter, description, |page| {
+            let image = skeletonize_page(&page, skeletonizer.as_ref(), reporter)?;
+            Ok(match config.prune {
+                Some(min_length) => prune_page(&image, min_length, reporter),
+                None => image
+            })
+        })
+    }
+}
 
-        println!("Skeletonization...");
-        skeletonizer.process(&mut binary_image);
+/// Rejects the pages the library can't skeletonize, it reads the neighbourhood
+/// of every pixel and fails on an empty page, e.g. a cropped region of no size
+pub fn check_page(page: &DynamicImage) -> CliResult<()> {
+    let (width, height) = page.dimensions();
+    if width == 0 || height == 0 {
+        return Err(CliError::InvalidArgument(format!("Can't skeletonize an empty {}x{} page", width, height)));
+    }
+    Ok(())
+}
 
-        println!("Converting to rgb...");
-        let result_image = binary_image.to_rgb_image();
+/// Binarizes and skeletonizes the page the way the library does
+pub fn skeletonize_page(
+    page: &DynamicImage,
+    skeletonizer: &dyn Skeletonizer,
+    reporter: &Reporter
+) -> CliResult<DynamicImage> {
+    check_page(page)?;
+    let image = page.to_rgb();
 
-        println!("Saving...");
-        result_image.save(config.output_file).unwrap_or_else(|err| {
-            eprintln!("Failed to save the resulting image: {}", err);
-            std::process::exit(1);
-        });
+    reporter.report("Converting the image to binary...");
+    let mut binary_image = reporter.time("binarization", || {
+        BinaryImage::from_image(&image, PixelColor::White)
+    });
 
-        println!("Done.");
-    }
+    reporter.report("Skeletonization...");
+    reporter.time("skeletonization", || skeletonizer.process(&mut binary_image));
+
+    reporter.report("Converting to rgb...");
+    let image = reporter.time("rgb_conversion", || binary_image.to_rgb_image());
+    Ok(DynamicImage::ImageRgb8(image))
+}
+
+/// Removes the branches shorter than `min_length` pixels from the skeleton
+pub fn prune_page(page: &DynamicImage, min_length: usize, reporter: &Reporter) -> DynamicImage {
+    reporter.report("Pruning spurs...");
+    let pruned = reporter.time("pruning", || {
+        imaging::prune_spurs(&BinaryGrid::from_image(page), min_length).to_rgb()
+    });
+    DynamicImage::ImageRgb8(pruned)
 }
@@ -16,6 +16,9 @@
 
 use clap::{ ArgMatches };
 use improc_petrsu::AdjacencyMode;
+use crate::cli::args::{ self, IoConfig };
+use crate::cli::error::{ CliError, CliResult };
+use crate::imaging::SkeletonizerVariant;
 
 
 #[derive(Clone, Copy)]
@@ -25,37 +28,70 @@ pub enum SkeletonizationAlgorithm {
     ZhangSuen
 }
 
+impl SkeletonizationAlgorithm {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SkeletonizationAlgorithm::Eberly => "Eberly",
+            SkeletonizationAlgorithm::Rosenfeld => "Rosenfeld",
+            SkeletonizationAlgorithm::ZhangSuen => "Zhang Suen"
+        }
+    }
+}
+
 pub struct SkeletonizeConfig<'a> {
-    pub input_file: &'a str,
-    pub output_file: &'a str,
+    pub io: IoConfig<'a>,
     pub algorithm: SkeletonizationAlgorithm, 
-    pub adjacency_mode: Option<AdjacencyMode>
+    pub adjacency_mode: Option<AdjacencyMode>,
+    /// Minimum length of the branches that end in an endpoint
+    pub prune: Option<usize>
+}
+
+/// Parses the 'algorithm' and 'adjacency-mode' arguments of the subcommands
+/// that skeletonize images
+pub fn parse_algorithm(matches: &ArgMatches) -> CliResult<(SkeletonizationAlgorithm, Option<AdjacencyMode>)> {
+    let algorithm = match matches.value_of("algorithm").unwrap() {
+        "Eberly" => SkeletonizationAlgorithm::Eberly,
+        "Rosenfeld" => SkeletonizationAlgorithm::Rosenfeld,
+        "ZhangSuen" => SkeletonizationAlgorithm::ZhangSuen,
+        arg => return Err(CliError::invalid_argument("algorithm", format!("unknown algorithm '{}'", arg)))
+    };
+    let adjacency_mode = match matches.value_of("adjacency-mode") {
+        Some("Four") => Some(AdjacencyMode::Four),
+        Some("Eight") => Some(AdjacencyMode::Eight),
+        Some(arg) => return Err(CliError::invalid_argument(
+            "adjacency-mode", format!("unknown adjacency mode '{}'", arg))),
+        None => None
+    };
+
+    Ok((algorithm, adjacency_mode))
+}
+
+/// Picks the skeletonizer, Rosenfeld requires an adjacency mode
+pub fn variant(
+    algorithm: SkeletonizationAlgorithm,
+    adjacency_mode: Option<AdjacencyMode>
+) -> CliResult<SkeletonizerVariant> {
+    match (algorithm, adjacency_mode) {
+        (SkeletonizationAlgorithm::Eberly, _) => Ok(SkeletonizerVariant::Eberly),
+        (SkeletonizationAlgorithm::ZhangSuen, _) => Ok(SkeletonizerVariant::ZhangSuen),
+        (SkeletonizationAlgorithm::Rosenfeld, Some(AdjacencyMode::Four)) => Ok(SkeletonizerVariant::RosenfeldFour),
+        (SkeletonizationAlgorithm::Rosenfeld, Some(AdjacencyMode::Eight)) => Ok(SkeletonizerVariant::RosenfeldEight),
+        (SkeletonizationAlgorithm::Rosenfeld, None) => Err(CliError::InvalidArgument(
+            "The Rosenfeld algorithm requires an adjacency mode".to_string()))
+    }
 }
 
 impl<'a> SkeletonizeConfig<'a> {
-    pub fn new(matches: &'a ArgMatches)-> Self {
-        let input_file = matches.value_of("input-file").unwrap();
-        let output_file = matches.value_of("output-file").unwrap();
-        let algorithm = match matches.value_of("algorithm").unwrap() {
-            "Eberly" => SkeletonizationAlgorithm::Eberly,
-            "Rosenfeld" => SkeletonizationAlgorithm::Rosenfeld,
-            "ZhangSuen" => SkeletonizationAlgorithm::ZhangSuen,
-            _ => panic!("Unknown algorithm")
-        };
-        let adjacency_mode = match matches.value_of("adjacency-mode") {
-            Some(arg) => match arg {
-                "Four" => Some(AdjacencyMode::Four),
-                "Eight" => Some(AdjacencyMode::Eight),
-                _ => panic!("Unknown adjacency mode")
-            },
-            None => None
-        };
-
-        SkeletonizeConfig {
-            input_file,    // Todo: use &str and lifetime instead
-            output_file, 
+    pub fn new(matches: &'a ArgMatches)-> CliResult<Self> {
+        let io = IoConfig::with_vector_output(matches)?;
+        let (algorithm, adjacency_mode) = parse_algorithm(matches)?;
+        let prune = args::parse_value(matches, "prune")?;
+
+        Ok(SkeletonizeConfig {
+            io,
             algorithm,
-            adjacency_mode
-        }
+            adjacency_mode,
+            prune
+        })
     }
 }
@@ -0,0 +1,84 @@
+// stdio.rs - Reads the input and writes the output, "-" stands for stdin/stdout
+// Copyright (C) 2019 Denis Karpovskiy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use image::{
+    DynamicImage, ImageError, ImageFormat, ImageResult,
+    error::{ ParameterError, ParameterErrorKind }
+};
+use crate::imaging::{ self, ImageMetadata };
+use std::fs;
+use std::io::{ self, Read, Write };
+
+pub const STDIO_PATH: &str = "-";
+
+pub fn read_input(path: &str) -> io::Result<Vec<u8>> {
+    if path == STDIO_PATH {
+        let mut bytes = Vec::new();
+        io::stdin().lock().read_to_end(&mut bytes)?;
+        Ok(bytes)
+    } else {
+        fs::read(path)
+    }
+}
+
+/// Saves the pages to the file or writes them to stdout. The format is
+/// mandatory for stdout and overrides the file extension otherwise.
+pub fn write_output(
+    path: &str,
+    pages: &[DynamicImage],
+    format: Option<ImageFormat>,
+    metadata: &ImageMetadata
+) -> ImageResult<()> {
+    if path != STDIO_PATH {
+        return imaging::save_pages(path, pages, format, metadata);
+    }
+
+    let format = format.ok_or_else(|| ImageError::Parameter(ParameterError::from_kind(
+        ParameterErrorKind::Generic("The output format is required for stdout".to_string()))))?;
+    let bytes = imaging::encode_pages(pages, format, metadata)?;
+
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    stdout.write_all(&bytes)?;
+    stdout.flush()?;
+    Ok(())
+}
+
+/// Writes a text result, e.g. a graph or a vector drawing, to the file or stdout
+pub fn write_text(path: &str, text: &str) -> io::Result<()> {
+    if path != STDIO_PATH {
+        return fs::write(path, text);
+    }
+
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    stdout.write_all(text.as_bytes())?;
+    stdout.flush()
+}
+
+/// Parses the value of the 'format' parameter, e.g. "png" or "tiff"
+pub fn parse_format(name: &str) -> Result<ImageFormat, String> {
+    match name.to_lowercase().as_str() {
+        "png" => Ok(ImageFormat::Png),
+        "jpg" | "jpeg" => Ok(ImageFormat::Jpeg),
+        "tif" | "tiff" => Ok(ImageFormat::Tiff),
+        "bmp" => Ok(ImageFormat::Bmp),
+        "gif" => Ok(ImageFormat::Gif),
+        "pnm" => Ok(ImageFormat::Pnm),
+        "tga" => Ok(ImageFormat::Tga),
+        _ => Err(format!("Unknown image format: '{}'", name))
+    }
+}
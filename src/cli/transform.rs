@@ -0,0 +1,129 @@
+// transform.rs - Runs an image to image operation over every page of the input
+// Copyright (C) 2019 Denis Karpovskiy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use image::{ DynamicImage, GenericImageView };
+use crate::imaging::{ self, BinaryGrid, ImageMetadata, VectorFormat, VectorOptions };
+use super::args::IoConfig;
+use super::error::{ CliError, CliResult };
+use super::reporter::Reporter;
+use super::stdio;
+
+// Traces the resulting skeleton into polylines
+fn write_vector(
+    config: &IoConfig,
+    pages: &[DynamicImage],
+    format: VectorFormat,
+    metadata: &ImageMetadata
+) -> CliResult<()> {
+    let page = match pages {
+        [page] => page,
+        _ => return Err(CliError::InvalidArgument(format!(
+            "Vector drawings hold a single page, but {} are selected", pages.len())))
+    };
+
+    let graph = imaging::trace_graph(&BinaryGrid::from_image(page));
+    let options = VectorOptions {
+        tolerance: config.vector_tolerance,
+        dpi: metadata.dpi.map(|(x, _)| x)
+    };
+    stdio::write_text(config.output_file, &imaging::encode_vector(&graph, format, &options))?;
+    Ok(())
+}
+
+/// Reads the input, applies the operation to every selected page and writes
+/// the result, recording the description in the processing history
+pub fn transform_pages<F>(
+    config: &IoConfig,
+    reporter: &Reporter,
+    description: String,
+    mut operation: F
+) -> CliResult<()>
+where
+    F: FnMut(DynamicImage) -> CliResult<DynamicImage>
+{
+    transform_document(config, reporter, description, |page, _| operation(page))
+}
+
+/// Like `transform_pages`, for operations that read or update the metadata
+/// of the document, such as its resolution. Every page starts from the metadata
+/// of the input, the updates of the last page are written to the output
+pub fn transform_document<F>(
+    config: &IoConfig,
+    reporter: &Reporter,
+    description: String,
+    mut operation: F
+) -> CliResult<()>
+where
+    F: FnMut(DynamicImage, &mut ImageMetadata) -> CliResult<DynamicImage>
+{
+    reporter.report("Opening the image...");
+    let bytes = stdio::read_input(config.input_file)?;
+    let document = reporter.time("decode", || {
+        imaging::load_document(&bytes, &config.pages, &config.load_options)
+    }).map_err(CliError::decoding)?;
+
+    let mut metadata = document.metadata;
+    metadata.history.push(match config.roi {
+        Some(roi) => format!("{} inside the region {}", description, roi),
+        None => description
+    });
+
+    // every page reads the resolution of the input, not the one of the previous page
+    let source_metadata = metadata.clone();
+    let mut updated_metadata = None;
+    let page_count = document.pages.len();
+    let mut results = Vec::with_capacity(page_count);
+    for (index, page) in document.pages.into_iter().enumerate() {
+        if page_count > 1 {
+            reporter.report(&format!("Page {} of {}...", index + 1, page_count));
+        }
+
+        reporter.set_page(Some(index));
+        let (width, height) = page.dimensions();
+        let foreground_before = imaging::count_foreground(&page);
+        let mut page_metadata = source_metadata.clone();
+        let result = match config.roi {
+            Some(roi) => {
+                let roi = roi.clamp(width, height).ok_or_else(|| CliError::InvalidArgument(
+                    format!("The region {} lies outside page {}", roi, index + 1)))?;
+                imaging::process_region(page, roi, config.crop_to_roi, |region| operation(region, &mut page_metadata))?
+            },
+            None => operation(page, &mut page_metadata)?
+        };
+        updated_metadata = Some(page_metadata);
+        reporter.add_page(width, height, foreground_before, imaging::count_foreground(&result));
+        results.push(result);
+    }
+    reporter.set_page(None);
+    if let Some(updated_metadata) = updated_metadata {
+        metadata = updated_metadata;
+    }
+
+    if let Some(dpi) = config.dpi {
+        metadata.dpi = Some((dpi, dpi));
+    }
+
+    reporter.report("Saving...");
+    match config.vector_format {
+        Some(format) => reporter.time("encode", || write_vector(config, &results, format, &metadata))?,
+        None => reporter.time("encode", || {
+            stdio::write_output(config.output_file, &results, config.output_format, &metadata)
+        }).map_err(CliError::encoding)?
+    }
+
+    reporter.report("Done.");
+    Ok(())
+}
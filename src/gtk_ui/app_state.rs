@@ -14,40 +14,124 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+use gdk_pixbuf::Pixbuf;
 use gtk::prelude::*;
 use gtk::{
     ApplicationWindow, Builder, Button, ComboBoxText, Dialog, FileChooserButton, Image, 
-    Notebook, SpinButton, MessageDialog,
+    Notebook, SpinButton, MessageDialog, Box as GtkBox, CheckButton, ColorButton, Statusbar, Grid,
+    EventBox, ShortcutsWindow,
 };
-use std::cell::{Ref, RefCell};
+use std::cell::{Cell, Ref, RefCell, RefMut};
+use crate::imaging::{ImageMetadata, Roi};
+use super::drawing::Stroke;
+use super::inspector::Inspector;
+use super::overlay::OverlayCache;
 
 pub struct AppState {
     // widgets
     pub main_window: ApplicationWindow,
     pub main_notebook: Notebook,
     pub file_chooser_button: FileChooserButton,
+    pub apply_orientation_check_button: CheckButton,
+    pub flatten_alpha_check_button: CheckButton,
+    pub background_color_button: ColorButton,
     pub image_view: Image,
+    pub image_event_box: EventBox,
+    pub preprocess_button: Button,
     pub convert_to_binary_button: Button,
+    pub invert_button: Button,
     pub skeletonize_button: Button,
+    pub prune_button: Button,
+    pub morphology_button: Button,
+    pub crop_button: Button,
+    pub geometry_button: Button,
+    pub drawing_tool_combo_box: ComboBoxText,
+    pub brush_size_spin_button: SpinButton,
+    pub compare_algorithms_button: Button,
+    pub analyze_button: Button,
+    pub components_button: Button,
+    pub show_graph_check_button: CheckButton,
+    pub show_medial_axis_check_button: CheckButton,
+    pub show_contours_check_button: CheckButton,
     pub undo_button: Button,
     pub save_button: Button,
+    pub save_all_pages_button: Button,
+    pub page_selector_box: GtkBox,
+    pub page_spin_button: SpinButton,
+    pub status_bar: Statusbar,
 
     // dialogs
     pub skeletonize_dialog: Dialog,
     pub convert_to_binary_dialog: Dialog,
+    pub preprocess_dialog: Dialog,
+    pub prune_dialog: Dialog,
+    pub morphology_dialog: Dialog,
+    pub geometry_dialog: Dialog,
+    pub compare_algorithms_dialog: Dialog,
+    pub components_dialog: Dialog,
     pub error_dialog: MessageDialog,
+    pub report_dialog: MessageDialog,
+    pub shortcuts_window: ShortcutsWindow,
 
     pub threshold_spin_button: SpinButton,
+    pub threshold_mode_combo_box: ComboBoxText,
+    pub threshold_channel_combo_box: ComboBoxText,
+    pub threshold_above_check_button: CheckButton,
+    pub threshold_color_button: ColorButton,
+    pub threshold_distance_spin_button: SpinButton,
+    pub preprocess_filter_combo_box: ComboBoxText,
+    pub preprocess_weights_combo_box: ComboBoxText,
+    pub preprocess_radius_spin_button: SpinButton,
+    pub preprocess_amount_spin_button: SpinButton,
+    pub prune_length_spin_button: SpinButton,
     pub skeletonize_algorithm_combo_box: ComboBoxText,
+    pub morphology_operation_combo_box: ComboBoxText,
+    pub morphology_shape_combo_box: ComboBoxText,
+    pub morphology_radius_spin_button: SpinButton,
+    pub morphology_area_spin_button: SpinButton,
+    pub geometry_operation_combo_box: ComboBoxText,
+    pub geometry_angle_spin_button: SpinButton,
+    pub geometry_dpi_spin_button: SpinButton,
+    pub components_adjacency_combo_box: ComboBoxText,
+    pub compare_algorithms_grid: Grid,
 
     // data
-    image_bytes: RefCell<Vec<u8>>,
-    snapshots: RefCell<Vec<ImageSnapshot>>,
+    pages: RefCell<Vec<PageState>>,
+    current_page: Cell<usize>,
+    // the region the operations apply to, in pixels of the image
+    selection: Cell<Option<Roi>>,
+    drag_start: Cell<Option<(u32, u32)>>,
+    // the scaled image shown without the selection
+    display_pixbuf: RefCell<Option<Pixbuf>>,
+    // the pencil, eraser or line stroke being drawn
+    stroke: RefCell<Option<Stroke>>,
+    // the next click on the image picks the reference colour of thresholding
+    picking_color: Cell<bool>,
+    // scale of the displayed image relative to its default height
+    zoom: Cell<f64>,
+    // the decoded latest image, describes the pixel under the pointer
+    inspector: RefCell<Option<Inspector>>,
 }
 
 pub struct ImageSnapshot {
     pub bytes: Vec<u8>,
     pub description: String,
+    /// Resampling changes the resolution, undoing it restores the previous one
+    pub dpi: Option<(f64, f64)>,
+    /// The image is a skeleton, the inspector classifies its pixels
+    pub skeleton: bool,
+}
+
+// Every page of a document keeps its own history
+#[derive(Default)]
+struct PageState {
+    image_bytes: Vec<u8>,
+    metadata: ImageMetadata,
+    snapshots: Vec<ImageSnapshot>,
+    // undone snapshots, a new snapshot discards them
+    undone: Vec<ImageSnapshot>,
+    // the overlays of the latest image
+    overlays: OverlayCache,
 }
 
 impl AppState {
@@ -55,62 +139,363 @@ impl AppState {
         let main_window: ApplicationWindow = builder.get_object("MainWindow")?;
         let main_notebook: Notebook = builder.get_object("MainNotebook")?;
         let file_chooser_button: FileChooserButton = builder.get_object("FileChooserButton")?;
+        let apply_orientation_check_button: CheckButton =
+            builder.get_object("ApplyOrientationCheckButton")?;
+        let flatten_alpha_check_button: CheckButton = builder.get_object("FlattenAlphaCheckButton")?;
+        let background_color_button: ColorButton = builder.get_object("BackgroundColorButton")?;
         let image_view: Image = builder.get_object("ImageView")?;
+        let image_event_box: EventBox = builder.get_object("ImageEventBox")?;
         let skeletonize_button: Button = builder.get_object("SkeletonizeButton")?;
+        let prune_button: Button = builder.get_object("PruneButton")?;
+        let morphology_button: Button = builder.get_object("MorphologyButton")?;
+        let crop_button: Button = builder.get_object("CropButton")?;
+        let geometry_button: Button = builder.get_object("GeometryButton")?;
+        let drawing_tool_combo_box: ComboBoxText = builder.get_object("DrawingToolComboBox")?;
+        let brush_size_spin_button: SpinButton = builder.get_object("BrushSizeSpinButton")?;
+        let preprocess_button: Button = builder.get_object("PreprocessButton")?;
         let convert_to_binary_button: Button = builder.get_object("ConvertToBinaryButton")?;
+        let invert_button: Button = builder.get_object("InvertButton")?;
+        let compare_algorithms_button: Button = builder.get_object("CompareAlgorithmsButton")?;
+        let analyze_button: Button = builder.get_object("AnalyzeButton")?;
+        let components_button: Button = builder.get_object("ComponentsButton")?;
+        let show_graph_check_button: CheckButton = builder.get_object("ShowGraphCheckButton")?;
+        let show_medial_axis_check_button: CheckButton =
+            builder.get_object("ShowMedialAxisCheckButton")?;
+        let show_contours_check_button: CheckButton = builder.get_object("ShowContoursCheckButton")?;
         let error_dialog: MessageDialog = builder.get_object("ErrorDialog")?;
+        let report_dialog: MessageDialog = builder.get_object("ReportDialog")?;
+        let shortcuts_window: ShortcutsWindow = builder.get_object("ShortcutsWindow")?;
         let undo_button: Button = builder.get_object("UndoButton")?;
         let save_button: Button = builder.get_object("SaveButton")?;
+        let save_all_pages_button: Button = builder.get_object("SaveAllPagesButton")?;
+        let page_selector_box: GtkBox = builder.get_object("PageSelectorBox")?;
+        let page_spin_button: SpinButton = builder.get_object("PageSpinButton")?;
+        let status_bar: Statusbar = builder.get_object("StatusBar")?;
 
         let skeletonize_dialog: Dialog = builder.get_object("SkeletonizeDialog")?;
         let convert_to_binary_dialog: Dialog = builder.get_object("ConvertToBinaryDialog")?;
+        let preprocess_dialog: Dialog = builder.get_object("PreprocessDialog")?;
+        let prune_dialog: Dialog = builder.get_object("PruneDialog")?;
+        let morphology_dialog: Dialog = builder.get_object("MorphologyDialog")?;
+        let geometry_dialog: Dialog = builder.get_object("GeometryDialog")?;
+        let compare_algorithms_dialog: Dialog = builder.get_object("CompareAlgorithmsDialog")?;
+        let components_dialog: Dialog = builder.get_object("ComponentsDialog")?;
 
         let threshold_spin_button: SpinButton = builder.get_object("ThresholdSpinButton")?;
+        let threshold_mode_combo_box: ComboBoxText = builder.get_object("ThresholdModeComboBox")?;
+        let threshold_channel_combo_box: ComboBoxText = builder.get_object("ThresholdChannelComboBox")?;
+        let threshold_above_check_button: CheckButton = builder.get_object("ThresholdAboveCheckButton")?;
+        let threshold_color_button: ColorButton = builder.get_object("ThresholdColorButton")?;
+        let threshold_distance_spin_button: SpinButton =
+            builder.get_object("ThresholdDistanceSpinButton")?;
+        let preprocess_filter_combo_box: ComboBoxText = builder.get_object("PreprocessFilterComboBox")?;
+        let preprocess_weights_combo_box: ComboBoxText = builder.get_object("PreprocessWeightsComboBox")?;
+        let preprocess_radius_spin_button: SpinButton = builder.get_object("PreprocessRadiusSpinButton")?;
+        let preprocess_amount_spin_button: SpinButton = builder.get_object("PreprocessAmountSpinButton")?;
+        let prune_length_spin_button: SpinButton = builder.get_object("PruneLengthSpinButton")?;
         let skeletonize_algorithm_combo_box = builder.get_object("SkeletonizeAlgorithmComboBox")?;
+        let morphology_operation_combo_box: ComboBoxText =
+            builder.get_object("MorphologyOperationComboBox")?;
+        let morphology_shape_combo_box: ComboBoxText = builder.get_object("MorphologyShapeComboBox")?;
+        let morphology_radius_spin_button: SpinButton =
+            builder.get_object("MorphologyRadiusSpinButton")?;
+        let morphology_area_spin_button: SpinButton = builder.get_object("MorphologyAreaSpinButton")?;
+        let geometry_operation_combo_box: ComboBoxText = builder.get_object("GeometryOperationComboBox")?;
+        let geometry_angle_spin_button: SpinButton = builder.get_object("GeometryAngleSpinButton")?;
+        let geometry_dpi_spin_button: SpinButton = builder.get_object("GeometryDpiSpinButton")?;
+        let components_adjacency_combo_box: ComboBoxText =
+            builder.get_object("ComponentsAdjacencyComboBox")?;
+        let compare_algorithms_grid: Grid = builder.get_object("CompareAlgorithmsGrid")?;
 
         Some(AppState {
             main_window,
             main_notebook,
             file_chooser_button,
+            apply_orientation_check_button,
+            flatten_alpha_check_button,
+            background_color_button,
             image_view,
+            image_event_box,
             skeletonize_button,
+            prune_button,
+            morphology_button,
+            crop_button,
+            geometry_button,
+            drawing_tool_combo_box,
+            brush_size_spin_button,
+            preprocess_button,
             convert_to_binary_button,
+            invert_button,
+            compare_algorithms_button,
+            analyze_button,
+            components_button,
+            show_graph_check_button,
+            show_medial_axis_check_button,
+            show_contours_check_button,
             error_dialog,
+            report_dialog,
+            shortcuts_window,
             undo_button,
             save_button,
+            save_all_pages_button,
+            page_selector_box,
+            page_spin_button,
+            status_bar,
 
             skeletonize_dialog,
             convert_to_binary_dialog,
+            preprocess_dialog,
+            prune_dialog,
+            morphology_dialog,
+            geometry_dialog,
+            compare_algorithms_dialog,
+            components_dialog,
             threshold_spin_button,
+            threshold_mode_combo_box,
+            threshold_channel_combo_box,
+            threshold_above_check_button,
+            threshold_color_button,
+            threshold_distance_spin_button,
+            preprocess_filter_combo_box,
+            preprocess_weights_combo_box,
+            preprocess_radius_spin_button,
+            preprocess_amount_spin_button,
+            prune_length_spin_button,
             skeletonize_algorithm_combo_box,
+            morphology_operation_combo_box,
+            morphology_shape_combo_box,
+            morphology_radius_spin_button,
+            morphology_area_spin_button,
+            geometry_operation_combo_box,
+            geometry_angle_spin_button,
+            geometry_dpi_spin_button,
+            components_adjacency_combo_box,
+            compare_algorithms_grid,
 
-            image_bytes: RefCell::new(Vec::new()),
-            snapshots: RefCell::new(Vec::new()),
+            pages: RefCell::new(vec![PageState::default()]),
+            current_page: Cell::new(0),
+            selection: Cell::new(None),
+            drag_start: Cell::new(None),
+            display_pixbuf: RefCell::new(None),
+            stroke: RefCell::new(None),
+            picking_color: Cell::new(false),
+            zoom: Cell::new(1.0),
+            inspector: RefCell::new(None),
         })
     }
 
     pub fn get_latest_image(&self) -> Ref<'_, Vec<u8>> {
-        if self.snapshots.borrow().len() > 0 {
-            Ref::map(self.snapshots.borrow(), |snaps| {
-                &snaps.last().unwrap().bytes
+        let index = self.current_page.get();
+        Ref::map(self.pages.borrow(), |pages| {
+            let page = &pages[index];
+            match page.snapshots.last() {
+                Some(snapshot) => &snapshot.bytes,
+                None => &page.image_bytes
+            }
+        })
+    }
+
+    /// The latest image of every page, in page order
+    pub fn get_latest_images(&self) -> Vec<Vec<u8>> {
+        self.pages
+            .borrow()
+            .iter()
+            .map(|page| match page.snapshots.last() {
+                Some(snapshot) => snapshot.bytes.clone(),
+                None => page.image_bytes.clone()
             })
-        } else {
-            self.image_bytes.borrow()
+            .collect()
+    }
+
+    /// The image the latest snapshot was made from, if there is a snapshot
+    pub fn get_previous_image(&self) -> Option<Vec<u8>> {
+        let pages = self.pages.borrow();
+        let page = &pages[self.current_page.get()];
+        match page.snapshots.len() {
+            0 => None,
+            1 => Some(page.image_bytes.clone()),
+            count => Some(page.snapshots[count - 2].bytes.clone())
         }
     }
 
     pub fn push_snapshot(&self, description: String, bytes: Vec<u8>) {
-        self.snapshots
-            .borrow_mut()
-            .push(ImageSnapshot { bytes, description });
+        let dpi = self.get_resolution();
+        self.push_resampled_snapshot(description, bytes, dpi);
+    }
+
+    /// Pushes a snapshot with a resolution of its own
+    pub fn push_resampled_snapshot(&self, description: String, bytes: Vec<u8>, dpi: Option<(f64, f64)>) {
+        self.push(ImageSnapshot { bytes, description, dpi, skeleton: false });
+    }
+
+    /// Pushes a snapshot holding a skeleton
+    pub fn push_skeleton_snapshot(&self, description: String, bytes: Vec<u8>) {
+        let dpi = self.get_resolution();
+        self.push(ImageSnapshot { bytes, description, dpi, skeleton: true });
+    }
+
+    fn push(&self, snapshot: ImageSnapshot) {
+        let mut pages = self.pages.borrow_mut();
+        let page = &mut pages[self.current_page.get()];
+        page.undone.clear();
+        page.snapshots.push(snapshot);
+        page.overlays = OverlayCache::default();
+    }
+
+    /// Whether the latest image of the current page is a skeleton
+    pub fn is_skeleton(&self) -> bool {
+        let pages = self.pages.borrow();
+        match pages[self.current_page.get()].snapshots.last() {
+            Some(snapshot) => snapshot.skeleton,
+            None => false
+        }
+    }
+
+    /// The resolution of the latest image of the current page
+    pub fn get_resolution(&self) -> Option<(f64, f64)> {
+        let pages = self.pages.borrow();
+        let page = &pages[self.current_page.get()];
+        match page.snapshots.last() {
+            Some(snapshot) => snapshot.dpi,
+            None => page.metadata.dpi
+        }
     }
     
-    pub fn pop_snapshot(&self) -> Option<ImageSnapshot> {
-        self.snapshots.borrow_mut().pop()
+    /// Undoes the latest snapshot, it's kept for redoing. Returns its description
+    pub fn pop_snapshot(&self) -> Option<String> {
+        let mut pages = self.pages.borrow_mut();
+        let page = &mut pages[self.current_page.get()];
+        let snapshot = page.snapshots.pop()?;
+        let description = snapshot.description.clone();
+        page.undone.push(snapshot);
+        page.overlays = OverlayCache::default();
+        Some(description)
+    }
+
+    /// Restores the latest undone snapshot. Returns its description
+    pub fn redo_snapshot(&self) -> Option<String> {
+        let mut pages = self.pages.borrow_mut();
+        let page = &mut pages[self.current_page.get()];
+        let snapshot = page.undone.pop()?;
+        let description = snapshot.description.clone();
+        page.snapshots.push(snapshot);
+        page.overlays = OverlayCache::default();
+        Some(description)
+    }
+
+    pub fn set_original_pages(&self, pages: Vec<Vec<u8>>, metadata: ImageMetadata) {
+        let pages = pages
+            .into_iter()
+            .map(|image_bytes| PageState {
+                image_bytes,
+                metadata: metadata.clone(),
+                snapshots: Vec::new(),
+                undone: Vec::new(),
+                overlays: OverlayCache::default()
+            })
+            .collect();
+        self.pages.replace(pages);
+        self.current_page.set(0);
+        self.selection.set(None);
+    }
+
+    /// Metadata of the current page with the snapshot descriptions
+    /// appended to its history
+    pub fn get_metadata(&self) -> ImageMetadata {
+        let pages = self.pages.borrow();
+        let page = &pages[self.current_page.get()];
+        let mut metadata = page.metadata.clone();
+        if let Some(snapshot) = page.snapshots.last() {
+            metadata.dpi = snapshot.dpi;
+        }
+        metadata
+            .history
+            .extend(page.snapshots.iter().map(|snapshot| snapshot.description.clone()));
+        metadata
+    }
+
+    /// Metadata for saving all pages into one file, the history
+    /// of every page is prefixed with its number
+    pub fn get_document_metadata(&self) -> ImageMetadata {
+        let pages = self.pages.borrow();
+        let mut metadata = pages[0].metadata.clone();
+        if let Some(snapshot) = pages[0].snapshots.last() {
+            metadata.dpi = snapshot.dpi;
+        }
+        for (index, page) in pages.iter().enumerate() {
+            metadata.history.extend(page.snapshots
+                .iter()
+                .map(|snapshot| format!("Page {}: {}", index + 1, snapshot.description)));
+        }
+        metadata
+    }
+
+    pub fn page_count(&self) -> usize {
+        self.pages.borrow().len()
+    }
+
+    pub fn set_current_page(&self, index: usize) {
+        if index < self.page_count() {
+            self.current_page.set(index);
+            self.selection.set(None);
+        }
+    }
+
+    pub fn get_selection(&self) -> Option<Roi> {
+        self.selection.get()
+    }
+
+    pub fn set_selection(&self, selection: Option<Roi>) {
+        self.selection.set(selection);
+    }
+
+    pub fn get_drag_start(&self) -> Option<(u32, u32)> {
+        self.drag_start.get()
+    }
+
+    pub fn set_drag_start(&self, start: Option<(u32, u32)>) {
+        self.drag_start.set(start);
+    }
+
+    pub fn get_display_pixbuf(&self) -> Option<Pixbuf> {
+        self.display_pixbuf.borrow().clone()
+    }
+
+    pub fn set_display_pixbuf(&self, pixbuf: Option<Pixbuf>) {
+        self.display_pixbuf.replace(pixbuf);
+    }
+
+    pub fn is_picking_color(&self) -> bool {
+        self.picking_color.get()
+    }
+
+    pub fn set_picking_color(&self, picking: bool) {
+        self.picking_color.set(picking);
+    }
+
+    pub fn get_stroke(&self) -> RefMut<'_, Option<Stroke>> {
+        self.stroke.borrow_mut()
+    }
+
+    pub fn get_zoom(&self) -> f64 {
+        self.zoom.get()
+    }
+
+    pub fn set_zoom(&self, zoom: f64) {
+        self.zoom.set(zoom);
+    }
+
+    pub fn get_inspector(&self) -> Ref<'_, Option<Inspector>> {
+        self.inspector.borrow()
+    }
+
+    pub fn set_inspector(&self, inspector: Option<Inspector>) {
+        self.inspector.replace(inspector);
     }
 
-    pub fn set_original_image(&self, bytes: Vec<u8>) {
-        self.snapshots.borrow_mut().clear();
-        self.image_bytes.replace(bytes);
+    /// The cached overlays of the latest image of the current page
+    pub fn get_overlays(&self) -> RefMut<'_, OverlayCache> {
+        let index = self.current_page.get();
+        RefMut::map(self.pages.borrow_mut(), |pages| &mut pages[index].overlays)
     }
 }
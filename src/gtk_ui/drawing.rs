@@ -0,0 +1,150 @@
+// drawing.rs - Pencil, eraser, line and flood fill tools used on the image view
+// Copyright (C) 2019 Denis Karpovskiy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use gdk_pixbuf::Pixbuf;
+use improc_petrsu::AdjacencyMode;
+use crate::imaging::{self, BinaryGrid};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DrawingTool {
+    /// Drags select the region the operations apply to
+    Select,
+    Pencil,
+    Eraser,
+    Line,
+    Fill,
+}
+
+impl DrawingTool {
+    /// Parses the ids of the tool combo box
+    pub fn from_id(id: &str) -> Self {
+        match id {
+            "pencil" => DrawingTool::Pencil,
+            "eraser" => DrawingTool::Eraser,
+            "line" => DrawingTool::Line,
+            "fill" => DrawingTool::Fill,
+            _ => DrawingTool::Select,
+        }
+    }
+}
+
+fn color_name(value: bool) -> &'static str {
+    if value {
+        "black"
+    } else {
+        "white"
+    }
+}
+
+// A stroke in progress, applied to the grid as the pointer moves and
+// previewed on a copy of the displayed pixbuf
+pub struct Stroke {
+    tool: DrawingTool,
+    // true paints the foreground
+    value: bool,
+    size: u32,
+    grid: BinaryGrid,
+    start: (u32, u32),
+    last: (u32, u32),
+    // the displayed pixbuf without the stroke and the image pixels per displayed pixel
+    base: Pixbuf,
+    scale: f64,
+    preview: Pixbuf,
+}
+
+impl Stroke {
+    pub fn begin(tool: DrawingTool, value: bool, size: u32, grid: BinaryGrid, start: (u32, u32), display: &Pixbuf)
+        -> Option<Self> {
+        let scale = display.get_height() as f64 / grid.height().max(1) as f64;
+        let mut stroke = Stroke {
+            tool,
+            value: value && tool != DrawingTool::Eraser,
+            size,
+            grid,
+            start,
+            last: start,
+            base: display.copy()?,
+            scale,
+            preview: display.copy()?,
+        };
+        stroke.extend(start);
+        Some(stroke)
+    }
+
+    pub fn preview(&self) -> &Pixbuf {
+        &self.preview
+    }
+
+    pub fn extend(&mut self, point: (u32, u32)) {
+        let to_i64 = |(x, y): (u32, u32)| (x as i64, y as i64);
+        if self.tool == DrawingTool::Line {
+            // the line is only drawn into the grid at the end
+            if let Some(preview) = self.base.copy() {
+                self.preview = preview;
+            }
+            self.draw_preview(self.start, point);
+        } else {
+            imaging::draw_line(&mut self.grid, to_i64(self.last), to_i64(point), self.size, self.value);
+            self.draw_preview(self.last, point);
+        }
+        self.last = point;
+    }
+
+    fn draw_preview(&self, from: (u32, u32), to: (u32, u32)) {
+        let to_view = |(x, y): (u32, u32)| ((x as f64 * self.scale) as i64, (y as f64 * self.scale) as i64);
+        let brush = imaging::brush_offsets((self.size as f64 * self.scale).round() as u32);
+        let (width, height) = (self.preview.get_width() as i64, self.preview.get_height() as i64);
+        let shade = if self.value { 0 } else { 255 };
+
+        for (x, y) in imaging::line_points(to_view(from), to_view(to)) {
+            for &(dx, dy) in &brush {
+                let (px, py) = (x + dx, y + dy);
+                if px >= 0 && py >= 0 && px < width && py < height {
+                    self.preview.put_pixel(px as _, py as _, shade, shade, shade, 255);
+                }
+            }
+        }
+    }
+
+    /// The edited image and the description of the stroke for the history
+    pub fn finish(mut self) -> (BinaryGrid, String) {
+        let description = match self.tool {
+            DrawingTool::Line => {
+                let (start, end) = (self.start, self.last);
+                imaging::draw_line(
+                    &mut self.grid,
+                    (start.0 as i64, start.1 as i64),
+                    (end.0 as i64, end.1 as i64),
+                    self.size,
+                    self.value,
+                );
+                format!(
+                    "Drew a {} pixel {} line from {},{} to {},{}",
+                    self.size, color_name(self.value), start.0, start.1, end.0, end.1
+                )
+            }
+            DrawingTool::Eraser => format!("Erased with a {} pixel eraser", self.size),
+            _ => format!("Drew with a {} pixel {} pencil", self.size, color_name(self.value)),
+        };
+        (self.grid, description)
+    }
+}
+
+/// Fills the region around the seed and describes it for the history
+pub fn fill(grid: &mut BinaryGrid, seed: (u32, u32), value: bool) -> String {
+    imaging::flood_fill(grid, seed, value, AdjacencyMode::Eight);
+    format!("Filled the region at {},{} with {}", seed.0, seed.1, color_name(value))
+}
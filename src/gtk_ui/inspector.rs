@@ -0,0 +1,77 @@
+// inspector.rs - Describes the pixel under the pointer for the status bar
+// Copyright (C) 2019 Denis Karpovskiy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use image::{DynamicImage, Pixel, RgbImage};
+use crate::imaging::{self, BinaryGrid};
+
+/// The decoded latest image, kept so that moving the pointer doesn't decode it again
+pub struct Inspector {
+    image: RgbImage,
+    grid: BinaryGrid,
+    // the image holds only black and white pixels
+    binary: bool,
+    // the image is a skeleton, its pixels are classified like the graph does
+    skeleton: bool,
+}
+
+impl Inspector {
+    pub fn new(bytes: &[u8], skeleton: bool) -> Option<Self> {
+        let image = image::load_from_memory(bytes).ok()?.to_rgb();
+        let grid = BinaryGrid::from_image(&DynamicImage::ImageRgb8(image.clone()));
+        let binary = imaging::is_binary(&image);
+        Some(Inspector { image, grid, binary, skeleton })
+    }
+
+    /// The dimensions and the zoom, followed by the pixel under the pointer if there is one
+    pub fn describe(&self, zoom: f64, point: Option<(u32, u32)>) -> String {
+        let mut text = format!(
+            "{} × {} px, zoom {:.0}%",
+            self.image.width(),
+            self.image.height(),
+            zoom * 100.0
+        );
+        let (x, y) = match point {
+            Some(point) => point,
+            None => return text,
+        };
+
+        let pixel = self.image.get_pixel(x, y);
+        text.push_str(&format!("  |  x {}, y {}  |  ", x, y));
+        if self.binary {
+            text.push_str(if pixel[0] == 0 { "black" } else { "white" });
+        } else {
+            text.push_str(&format!(
+                "RGB {}, {}, {}, gray {}",
+                pixel[0],
+                pixel[1],
+                pixel[2],
+                pixel.to_luma()[0]
+            ));
+        }
+
+        let foreground = self.grid.at(x, y);
+        text.push_str(if foreground { ", foreground" } else { ", background" });
+        if self.skeleton && foreground {
+            let kind = imaging::classify_pixel(&self.grid, x, y);
+            text.push_str(&format!(
+                ", {} neighbours ({})",
+                self.grid.neighbour_count(x, y),
+                kind.name()
+            ));
+        }
+        text
+    }
+}
@@ -15,24 +15,37 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 mod app_state;
+mod drawing;
+mod inspector;
+mod overlay;
 
 use app_state::AppState;
+use drawing::{DrawingTool, Stroke};
+use inspector::Inspector;
 use gdk_pixbuf::Pixbuf;
 use gio::prelude::*;
-use gio::{Cancellable, MemoryInputStream};
+use gio::{Cancellable, MemoryInputStream, SimpleAction};
 use glib::Bytes;
 use gtk::prelude::*;
-use gtk::{Application, Builder, FileChooserExt, NotebookExt, ResponseType, WidgetExt,
-    FileChooserNative, FileChooserAction};
-use image::{DynamicImage, ImageOutputFormat};
+use gtk::{Application, Builder, FileChooserExt, Inhibit, NotebookExt, ResponseType, WidgetExt,
+    FileChooserNative, FileChooserAction, FileFilter, Button, Image, Label, Orientation};
+use image::{DynamicImage, GenericImageView, ImageDecoder, ImageFormat, ImageOutputFormat, Pixel, Rgb,
+    RgbImage};
+use image::png::PngDecoder;
 use improc_petrsu::{
     AdjacencyMode, BinaryImage, BinaryImageConverter, EberlySkeletonizer, PixelColor,
     RosenfeldSkeletonizer, Skeletonizer, ThresholdBinaryImageConverter, ZhangSuenSkeletonizer,
 };
+use crate::imaging::{self, BinaryGrid, Channel, ChannelWeights, ComponentStats, Components, ElementShape,
+    FlipDirection, GeometricTransform, LoadOptions, MorphologyOperation, PageRange, PreprocessFilter, Roi,
+    SkeletonizerVariant, StructuringElement, ThresholdMode, VectorFormat, VectorOptions};
+use crate::profiler::{self, StageProfile};
 use std::env;
 use std::fs::File;
-use std::io::Read;
+use std::io::{Cursor, Read};
 use std::rc::Rc;
+use std::thread;
+use std::time::{Duration, Instant};
 use std::path::Path;
 use std::fs::OpenOptions;
 use std::io::Write;
@@ -44,22 +57,201 @@ fn open_error_dialog<S: AsRef<str>>(app_state: Rc<AppState>, message: S) {
     app_state.error_dialog.hide();
 }
 
-fn update_image(app_state: Rc<AppState>) {
-    info!("Updating the GtkImage");
-    let bytes = Bytes::from(&*app_state.get_latest_image());
+// shows the time and the memory every stage of the last operation took
+fn show_profile(app_state: Rc<AppState>, profiles: &[StageProfile]) {
+    let text = profiles
+        .iter()
+        .map(|profile| profile.to_string())
+        .collect::<Vec<_>>()
+        .join(" | ");
+    let context_id = app_state.status_bar.get_context_id("profile");
+    app_state.status_bar.remove_all(context_id);
+    app_state.status_bar.push(context_id, &text);
+}
+
+// height of the displayed image at 100% zoom
+const DISPLAY_HEIGHT: f64 = 400.0;
+const ZOOM_STEP: f64 = 1.25;
+const MIN_ZOOM: f64 = 0.25;
+const MAX_ZOOM: f64 = 8.0;
+
+fn pixbuf_from_bytes(bytes: &[u8], height: i32) -> Result<Pixbuf, glib::Error> {
+    let bytes = Bytes::from(bytes);
     let stream = MemoryInputStream::new_from_bytes(&bytes);
     let cancellable = Cancellable::new();
-    match Pixbuf::new_from_stream_at_scale(&stream, -1, 400, true, Some(&cancellable)) {
+    Pixbuf::new_from_stream_at_scale(&stream, -1, height, true, Some(&cancellable))
+}
+
+// the latest image with the enabled overlays drawn over it, the overlays
+// are cached until the latest image changes
+fn display_image(app_state: &AppState) -> Vec<u8> {
+    let bytes = app_state.get_latest_image().clone();
+    // the graph of anything but a skeleton means nothing
+    let show_graph = app_state.show_graph_check_button.get_active() && app_state.is_skeleton();
+    let show_medial_axis = app_state.show_medial_axis_check_button.get_active();
+    let show_contours = app_state.show_contours_check_button.get_active();
+    if !show_graph && !show_medial_axis && !show_contours {
+        return bytes;
+    }
+
+    let shown = (show_graph, show_medial_axis, show_contours);
+    let mut overlays = app_state.get_overlays();
+    if let Some((rendered_shown, rendered)) = &overlays.rendered {
+        if *rendered_shown == shown {
+            return rendered.clone();
+        }
+    }
+
+    let mut image = match image::load_from_memory(&bytes) {
+        Ok(image) => image.to_rgb(),
+        Err(_) => return bytes,
+    };
+    let grid = BinaryGrid::from_image(&DynamicImage::ImageRgb8(image.clone()));
+
+    if show_medial_axis {
+        let axis = overlays.medial_axis.get_or_insert_with(|| {
+            info!("Computing the medial axis...");
+            let mut skeleton = BinaryImage::from_image(&image, PixelColor::White);
+            SkeletonizerVariant::ZhangSuen.create().process(&mut skeleton);
+            let skeleton = BinaryGrid::from_image(&DynamicImage::ImageRgb8(skeleton.to_rgb_image()));
+            imaging::medial_axis(&grid, &skeleton)
+        });
+        overlay::draw_medial_axis(&mut image, axis);
+    }
+
+    if show_contours {
+        let contours = overlays.contours.get_or_insert_with(|| {
+            info!("Following the contours...");
+            imaging::trace_contours(&grid)
+        });
+        overlay::draw_contours(&mut image, contours);
+    }
+
+    if show_graph {
+        let graph = overlays.graph.get_or_insert_with(|| {
+            info!("Tracing the skeleton graph...");
+            imaging::trace_graph(&grid)
+        });
+        overlay::draw_graph(&mut image, graph);
+    }
+
+    match imaging::encode_png(&DynamicImage::ImageRgb8(image)) {
+        Ok(rendered) => {
+            overlays.rendered = Some((shown, rendered.clone()));
+            rendered
+        }
+        Err(_) => bytes,
+    }
+}
+
+// size of the current image, the snapshots are always PNG
+fn image_dimensions(app_state: &AppState) -> Option<(u32, u32)> {
+    let bytes = app_state.get_latest_image();
+    PngDecoder::new(Cursor::new(bytes.as_slice()))
+        .ok()
+        .map(|decoder| decoder.dimensions())
+}
+
+// maps a point of the image view to the pixel of the image under it
+fn view_to_image(app_state: &AppState, (x, y): (f64, f64)) -> Option<(u32, u32)> {
+    let pixbuf = app_state.get_display_pixbuf()?;
+    let (width, height) = image_dimensions(app_state)?;
+    let (view_width, view_height) = (pixbuf.get_width() as f64, pixbuf.get_height() as f64);
+
+    // GtkImage centres the pixbuf in its allocation
+    let left = (app_state.image_view.get_allocated_width() as f64 - view_width) / 2.0;
+    let top = (app_state.image_view.get_allocated_height() as f64 - view_height) / 2.0;
+    let image_x = ((x - left) / view_width * width as f64).floor();
+    let image_y = ((y - top) / view_height * height as f64).floor();
+
+    Some((
+        image_x.clamp(0.0, width as f64 - 1.0) as u32,
+        image_y.clamp(0.0, height as f64 - 1.0) as u32,
+    ))
+}
+
+// the pixel under the pointer, none when the pointer is beside the displayed image
+fn pixel_under_pointer(app_state: &AppState, (x, y): (f64, f64)) -> Option<(u32, u32)> {
+    let pixbuf = app_state.get_display_pixbuf()?;
+    let (view_width, view_height) = (pixbuf.get_width() as f64, pixbuf.get_height() as f64);
+    let left = (app_state.image_view.get_allocated_width() as f64 - view_width) / 2.0;
+    let top = (app_state.image_view.get_allocated_height() as f64 - view_height) / 2.0;
+    if x < left || y < top || x >= left + view_width || y >= top + view_height {
+        return None;
+    }
+    view_to_image(app_state, (x, y))
+}
+
+// shows the dimensions, the zoom and the pixel under the pointer in the status bar
+fn show_inspector(app_state: &AppState, position: Option<(f64, f64)>) {
+    let context_id = app_state.status_bar.get_context_id("inspector");
+    app_state.status_bar.remove_all(context_id);
+    // keep the eyedropper hint visible while picking
+    if app_state.is_picking_color() {
+        return;
+    }
+
+    let inspector = app_state.get_inspector();
+    let (inspector, pixbuf) = match (inspector.as_ref(), app_state.get_display_pixbuf()) {
+        (Some(inspector), Some(pixbuf)) => (inspector, pixbuf),
+        _ => return,
+    };
+    let zoom = match image_dimensions(app_state) {
+        Some((_, height)) if height > 0 => pixbuf.get_height() as f64 / height as f64,
+        _ => 1.0,
+    };
+    let point = position.and_then(|position| pixel_under_pointer(app_state, position));
+    app_state.status_bar.push(context_id, &inspector.describe(zoom, point));
+}
+
+// shows the displayed image with the selection drawn over it
+fn show_selection(app_state: &AppState) {
+    let pixbuf = match app_state.get_display_pixbuf() {
+        Some(pixbuf) => pixbuf,
+        None => return,
+    };
+
+    let shown = match (app_state.get_selection(), image_dimensions(app_state), pixbuf.copy()) {
+        (Some(selection), Some((_, height)), Some(copy)) => {
+            overlay::draw_selection(&copy, selection, pixbuf.get_height() as f64 / height as f64);
+            copy
+        }
+        _ => pixbuf,
+    };
+    app_state.image_view.set_from_pixbuf(Some(&shown));
+}
+
+// the selection clipped to the image, none when nothing is selected
+fn selected_region(app_state: &AppState, image: &DynamicImage) -> Option<Roi> {
+    let (width, height) = image.dimensions();
+    app_state.get_selection().and_then(|selection| selection.clamp(width, height))
+}
+
+fn describe_region(description: String, region: Option<Roi>) -> String {
+    match region {
+        Some(region) => format!("{} inside the region {}", description, region),
+        None => description,
+    }
+}
+
+fn update_image(app_state: Rc<AppState>) {
+    info!("Updating the GtkImage");
+    let height = (DISPLAY_HEIGHT * app_state.get_zoom()).round() as i32;
+    app_state.show_graph_check_button.set_sensitive(app_state.is_skeleton());
+    match pixbuf_from_bytes(&display_image(&app_state), height) {
         Ok(p) => {
-            app_state.image_view.set_from_pixbuf(Some(&p));
+            app_state.set_display_pixbuf(Some(p));
+            show_selection(&app_state);
+            let inspector = Inspector::new(&app_state.get_latest_image(), app_state.is_skeleton());
+            app_state.set_inspector(inspector);
+            show_inspector(&app_state, None);
         }
         Err(_) => open_error_dialog(app_state, "Error converting the stream to pixbuf")
     };
 }
 
-fn save_image<P: AsRef<Path>>(app_state: Rc<AppState>, path: P) {
+fn save_image<P: AsRef<Path>>(app_state: Rc<AppState>, path: P, bytes: &[u8]) {
     info!("Saving the image in: {}", path.as_ref().to_string_lossy());
-    let bytes = app_state.get_latest_image();
     let mut file = match OpenOptions::new()
         .write(true)
         .create_new(true)
@@ -71,7 +263,7 @@ fn save_image<P: AsRef<Path>>(app_state: Rc<AppState>, path: P) {
         }
     };
 
-    if file.write_all(&*bytes).is_err() {
+    if file.write_all(bytes).is_err() {
         open_error_dialog(app_state.clone(), "Couldn't write the image to the file");
         return;
     }
@@ -110,51 +302,224 @@ fn file_set_handler(app_state: Rc<AppState>) {
         return;
     }
 
-    app_state.set_original_image(buf);
+    let background = if app_state.flatten_alpha_check_button.get_active() {
+        let color = app_state.background_color_button.get_rgba();
+        let channel = |value: f64| (value * 255.0).round() as u8;
+        Some(Rgb([channel(color.red), channel(color.green), channel(color.blue)]))
+    } else {
+        None
+    };
+    let options = LoadOptions {
+        apply_orientation: app_state.apply_orientation_check_button.get_active(),
+        background
+    };
+
+    info!("Decoding the pages...");
+    let document = match imaging::load_document(&buf, &PageRange::All, &options) {
+        Ok(document) => document,
+        Err(_) => {
+            open_error_dialog(app_state, "Unable to decode the image");
+            return;
+        }
+    };
+
+    let pages = match document.pages.iter().map(imaging::encode_png).collect::<Result<Vec<_>, _>>() {
+        Ok(pages) => pages,
+        Err(_) => {
+            open_error_dialog(app_state, "Couldn't write the image as PNG");
+            return;
+        }
+    };
+
+    app_state.set_original_pages(pages, document.metadata);
+
+    let page_count = app_state.page_count();
+    app_state.page_spin_button.set_range(1.0, page_count as f64);
+    app_state.page_spin_button.set_value(1.0);
+    app_state.page_selector_box.set_visible(page_count > 1);
+    app_state.save_all_pages_button.set_visible(page_count > 1);
 
     update_image(app_state.clone());
 
     app_state.main_notebook.next_page();
 }
 
+// runs the preprocessing dialog and filters the latest image with the chosen filter
+fn preprocess_handler(app_state: Rc<AppState>) {
+    let response = app_state.preprocess_dialog.run();
+    app_state.preprocess_dialog.hide();
+    if response != ResponseType::Ok {
+        return;
+    }
+
+    let radius = app_state.preprocess_radius_spin_button.get_value();
+    let amount = app_state.preprocess_amount_spin_button.get_value();
+    let filter = match app_state.preprocess_filter_combo_box.get_active_id().unwrap().as_str() {
+        "grayscale" => {
+            let weights = app_state.preprocess_weights_combo_box.get_active_id().unwrap();
+            PreprocessFilter::Grayscale(ChannelWeights::from_name(&weights).unwrap())
+        }
+        "gaussian-blur" => PreprocessFilter::GaussianBlur(radius as f32),
+        "median-blur" => PreprocessFilter::MedianBlur(radius.round().max(1.0) as u32),
+        "stretch" => PreprocessFilter::StretchContrast(amount),
+        "gamma" => PreprocessFilter::Gamma(amount),
+        "equalize" => PreprocessFilter::EqualizeHistogram,
+        _ => PreprocessFilter::SubtractBackground(radius.round().max(1.0) as u32),
+    };
+
+    info!("Loading image into memory...");
+    let image = match image::load_from_memory(&app_state.get_latest_image()) {
+        Ok(img) => img,
+        Err(_) => {
+            open_error_dialog(app_state.clone(), "Couldn't read the image bytes");
+            return;
+        }
+    };
+
+    info!("Preprocessing the image...");
+    let region = selected_region(&app_state, &image);
+    let result = match region {
+        Some(region) => region.paste(&image, &filter.apply(&region.crop(&image))),
+        None => filter.apply(&image),
+    };
+
+    info!("Writing the result as byte array...");
+    match imaging::encode_png(&result) {
+        Ok(bytes) => {
+            app_state.push_snapshot(describe_region(filter.description(), region), bytes);
+            update_image(app_state.clone());
+        }
+        Err(_) => open_error_dialog(app_state.clone(), "Couldn't write the image as PNG"),
+    }
+}
+
+// thresholding on a channel or a colour range, none for the brightness
+fn threshold_mode(app_state: &AppState, threshold: u8) -> Option<ThresholdMode> {
+    match app_state.threshold_mode_combo_box.get_active_id().as_deref() {
+        Some("channel") => {
+            let channel = app_state.threshold_channel_combo_box.get_active_id().unwrap();
+            Some(ThresholdMode::Channel {
+                channel: Channel::from_name(&channel).unwrap(),
+                threshold,
+                above: app_state.threshold_above_check_button.get_active(),
+            })
+        }
+        Some("color-range") => {
+            let color = app_state.threshold_color_button.get_rgba();
+            let channel = |value: f64| (value * 255.0).round() as u8;
+            Some(ThresholdMode::ColorRange {
+                color: Rgb([channel(color.red), channel(color.green), channel(color.blue)]),
+                distance: app_state.threshold_distance_spin_button.get_value(),
+            })
+        }
+        _ => None,
+    }
+}
+
+// sets the reference colour of thresholding to the pixel under the pointer
+fn pick_color(app_state: &AppState, (x, y): (u32, u32)) {
+    let pixel = match image::load_from_memory(&app_state.get_latest_image()) {
+        Ok(image) => image.get_pixel(x, y).to_rgb(),
+        Err(_) => return,
+    };
+
+    let mut color = app_state.threshold_color_button.get_rgba();
+    color.red = pixel[0] as f64 / 255.0;
+    color.green = pixel[1] as f64 / 255.0;
+    color.blue = pixel[2] as f64 / 255.0;
+    color.alpha = 1.0;
+    app_state.threshold_color_button.set_rgba(&color);
+    app_state.threshold_mode_combo_box.set_active_id(Some("color-range"));
+
+    let context_id = app_state.status_bar.get_context_id("eyedropper");
+    app_state.status_bar.remove_all(context_id);
+    app_state.status_bar.push(
+        context_id,
+        &format!("Picked #{:02x}{:02x}{:02x} at {},{}", pixel[0], pixel[1], pixel[2], x, y),
+    );
+}
+
 // when the 'convert to binary' button clicked we should display a modal window
 // waiting for the user to specify threshold, and
 fn convert_to_binary_handler(app_state: Rc<AppState>) {
     app_state.threshold_spin_button.set_value(125 as f64);
-    if app_state.convert_to_binary_dialog.run() == ResponseType::Ok {
+    run_convert_to_binary_dialog(app_state);
+}
+
+// runs the dialog with the values it has, picking a colour reopens it this way
+fn run_convert_to_binary_dialog(app_state: Rc<AppState>) {
+    let response = app_state.convert_to_binary_dialog.run();
+    if response == ResponseType::Other(1) {
+        // the dialog opens again once the colour is picked
+        app_state.convert_to_binary_dialog.hide();
+        app_state.set_picking_color(true);
+        let context_id = app_state.status_bar.get_context_id("eyedropper");
+        app_state.status_bar.remove_all(context_id);
+        app_state.status_bar.push(context_id, "Click the image to pick the reference colour");
+        return;
+    }
+
+    if response == ResponseType::Ok {
         let threshold = app_state.threshold_spin_button.get_value();
         if threshold < 0.0 || threshold > 255.0 {
             panic!("threshold must be between 0 and 255 inclusive!");
         }
 
+        let mut profiles = Vec::new();
+
         info!("Loading image into memory...");
-        let mut image = match image::load_from_memory(&app_state.get_latest_image()) {
-            Ok(img) => img.to_rgb(),
+        let (image, profile) = profiler::profile("decode", || {
+            image::load_from_memory(&app_state.get_latest_image())
+        });
+        profiles.push(profile);
+        let image = match image {
+            Ok(img) => img,
             Err(_) => {
                 open_error_dialog(app_state.clone(), "Couldn't read the image bytes");
                 return;
             }
         };
+        let region = selected_region(&app_state, &image);
+        let source = match region {
+            Some(region) => region.crop(&image),
+            None => image.clone(),
+        };
 
+        let mode = threshold_mode(&app_state, threshold as u8);
         let converter = ThresholdBinaryImageConverter::new(threshold as u32);
 
         info!("Converting the image to binary...");
-        converter.convert_to_binary(&mut image);
-        let dyn_image = DynamicImage::ImageRgb8(image);
+        let (binary_image, profile) = profiler::profile("binarization", || match mode {
+            Some(mode) => mode.apply(&source),
+            None => {
+                let mut binary_image = source.to_rgb();
+                converter.convert_to_binary(&mut binary_image);
+                binary_image
+            }
+        });
+        profiles.push(profile);
+        let dyn_image = match region {
+            Some(region) => region.paste(&image, &DynamicImage::ImageRgb8(binary_image)),
+            None => DynamicImage::ImageRgb8(binary_image),
+        };
         let mut vector = Vec::new();
 
         info!("Writing the result as byte array...");
-        if dyn_image
-            .write_to(&mut vector, ImageOutputFormat::Png)
-            .is_err() {
+        let (result, profile) = profiler::profile("encode", || {
+            dyn_image.write_to(&mut vector, ImageOutputFormat::Png)
+        });
+        profiles.push(profile);
+        if result.is_err() {
             open_error_dialog(app_state, "Couldn't write the image as PNG");
             return;
         }
 
-        app_state.push_snapshot(
-            format!("Converted to binary with threshold: {}", threshold),
-            vector,
-        );
+        show_profile(app_state.clone(), &profiles);
+        let description = match mode {
+            Some(mode) => mode.description(),
+            None => format!("Converted to binary with threshold: {}", threshold),
+        };
+        app_state.push_snapshot(describe_region(description, region), vector);
 
         update_image(app_state.clone());
     };
@@ -183,34 +548,57 @@ fn skeletonize_handler(app_state: Rc<AppState>) {
             _ => panic!("Unknow skeletonizer value!"),
         };
 
+        let mut profiles = Vec::new();
+
         info!("Loading image into memory...");
-        let image = match image::load_from_memory(&app_state.get_latest_image()) {
-            Ok(img) => img.to_rgb(),
+        let (image, profile) = profiler::profile("decode", || {
+            image::load_from_memory(&app_state.get_latest_image())
+        });
+        profiles.push(profile);
+        let image = match image {
+            Ok(img) => img,
             Err(_) => {
                 open_error_dialog(app_state.clone(), "Couldn't read the image bytes");
                 return;
             }
         };
+        let region = selected_region(&app_state, &image);
+        let rgb_image = match region {
+            Some(region) => region.crop(&image).to_rgb(),
+            None => image.to_rgb(),
+        };
 
         info!("Converting the image to binary...");
-        let mut binary_image = BinaryImage::from_image(&image, PixelColor::White);
+        let (mut binary_image, profile) = profiler::profile("binarization", || {
+            BinaryImage::from_image(&rgb_image, PixelColor::White)
+        });
+        profiles.push(profile);
 
         info!("Skeletonizing the image...");
-        skeletonizer.process(&mut binary_image);
+        let ((), profile) = profiler::profile("skeletonization", || skeletonizer.process(&mut binary_image));
+        profiles.push(profile);
 
         info!("Converting the image to RGB...");
-        let ret = binary_image.to_rgb_image();
-        let dyn_image = DynamicImage::ImageRgb8(ret);
+        let (ret, profile) = profiler::profile("rgb_conversion", || binary_image.to_rgb_image());
+        profiles.push(profile);
+        let dyn_image = match region {
+            Some(region) => region.paste(&image, &DynamicImage::ImageRgb8(ret)),
+            None => DynamicImage::ImageRgb8(ret),
+        };
         let mut vector = Vec::new();
 
         info!("Writing the image as byte array...");
-        if dyn_image
-            .write_to(&mut vector, ImageOutputFormat::Png)
-            .is_err() {
+        let (result, profile) = profiler::profile("encode", || {
+            dyn_image.write_to(&mut vector, ImageOutputFormat::Png)
+        });
+        profiles.push(profile);
+        if result.is_err() {
             open_error_dialog(app_state, "Couldn't write the image as PNG");
             return;
         }
 
+        show_profile(app_state.clone(), &profiles);
+
         let algorithm_str = match algorithm {
             0 => "Rosenfeld",
             1 => "Eberly",
@@ -218,10 +606,10 @@ fn skeletonize_handler(app_state: Rc<AppState>) {
             _ => panic!("Unknown skeletonizer value!"),
         };
 
-        app_state.push_snapshot(
-            format!(
-                "Skeletonized the image with the {} algorithm",
-                algorithm_str
+        app_state.push_skeleton_snapshot(
+            describe_region(
+                format!("Skeletonized the image with the {} algorithm", algorithm_str),
+                region,
             ),
             vector,
         );
@@ -232,16 +620,617 @@ fn skeletonize_handler(app_state: Rc<AppState>) {
     app_state.skeletonize_dialog.hide();
 }
 
+fn morphology_handler(app_state: Rc<AppState>) {
+    if app_state.morphology_dialog.run() == ResponseType::Ok {
+        let operation = app_state.morphology_operation_combo_box.get_active_id().unwrap();
+        let shape = app_state.morphology_shape_combo_box.get_active_id().unwrap();
+        let element = StructuringElement {
+            shape: ElementShape::from_name(&shape).unwrap(),
+            radius: app_state.morphology_radius_spin_button.get_value() as u32,
+        };
+        let area = app_state.morphology_area_spin_button.get_value() as usize;
+
+        info!("Loading image into memory...");
+        let image = match image::load_from_memory(&app_state.get_latest_image()) {
+            Ok(img) => img,
+            Err(_) => {
+                open_error_dialog(app_state.clone(), "Couldn't read the image bytes");
+                app_state.morphology_dialog.hide();
+                return;
+            }
+        };
+
+        info!("Cleaning up the binary image...");
+        let grid = BinaryGrid::binarize(&image);
+        let (result, description) = match operation.as_str() {
+            "remove-small" => (
+                imaging::remove_small_components(&grid, area, AdjacencyMode::Eight),
+                format!("Removed components smaller than {} pixels", area),
+            ),
+            "fill-holes" => (
+                imaging::fill_small_holes(&grid, area, AdjacencyMode::Eight),
+                format!("Filled holes smaller than {} pixels", area),
+            ),
+            name => {
+                let operation = MorphologyOperation::from_name(name).unwrap();
+                (
+                    imaging::morphology(&grid, operation, &element),
+                    format!(
+                        "Applied {} with a {} of radius {}",
+                        operation.name(),
+                        element.shape.name(),
+                        element.radius
+                    ),
+                )
+            }
+        };
+
+        info!("Writing the result as byte array...");
+        match imaging::encode_png(&DynamicImage::ImageRgb8(result.to_rgb())) {
+            Ok(bytes) => {
+                app_state.push_snapshot(description, bytes);
+                update_image(app_state.clone());
+            }
+            Err(_) => open_error_dialog(app_state.clone(), "Couldn't write the image as PNG"),
+        }
+    }
+
+    app_state.morphology_dialog.hide();
+}
+
+fn prune_handler(app_state: Rc<AppState>) {
+    if app_state.prune_dialog.run() == ResponseType::Ok {
+        let min_length = app_state.prune_length_spin_button.get_value() as usize;
+
+        info!("Loading image into memory...");
+        let image = match image::load_from_memory(&app_state.get_latest_image()) {
+            Ok(img) => img,
+            Err(_) => {
+                open_error_dialog(app_state.clone(), "Couldn't read the image bytes");
+                app_state.prune_dialog.hide();
+                return;
+            }
+        };
+
+        info!("Pruning spurs...");
+        let pruned = imaging::prune_spurs(&BinaryGrid::from_image(&image), min_length).to_rgb();
+
+        info!("Writing the result as byte array...");
+        match imaging::encode_png(&DynamicImage::ImageRgb8(pruned)) {
+            Ok(bytes) => {
+                app_state.push_skeleton_snapshot(format!("Pruned spurs shorter than {} pixels", min_length), bytes);
+                update_image(app_state.clone());
+            }
+            Err(_) => open_error_dialog(app_state.clone(), "Couldn't write the image as PNG"),
+        }
+    }
+
+    app_state.prune_dialog.hide();
+}
+
+struct ComparisonResult {
+    variant: SkeletonizerVariant,
+    bytes: Vec<u8>,
+    pixels: u64,
+    elapsed: Duration,
+}
+
+// runs every skeletonizer on the current image in its own thread
+fn compare_algorithms(image: &RgbImage) -> Vec<Option<ComparisonResult>> {
+    let handles = SkeletonizerVariant::ALL
+        .iter()
+        .map(|&variant| {
+            let image = image.clone();
+            thread::spawn(move || {
+                let skeletonizer = variant.create();
+                let mut binary_image = BinaryImage::from_image(&image, PixelColor::White);
+
+                let start = Instant::now();
+                skeletonizer.process(&mut binary_image);
+                let elapsed = start.elapsed();
+
+                let result = DynamicImage::ImageRgb8(binary_image.to_rgb_image());
+                let pixels = imaging::count_foreground(&result);
+                imaging::encode_png(&result)
+                    .ok()
+                    .map(|bytes| ComparisonResult { variant, bytes, pixels, elapsed })
+            })
+        })
+        .collect::<Vec<_>>();
+
+    // a panicking skeletonizer only loses its own cell
+    handles
+        .into_iter()
+        .map(|handle| handle.join().ok().flatten())
+        .collect()
+}
+
+fn compare_algorithms_handler(app_state: Rc<AppState>) {
+    info!("Loading image into memory...");
+    let image = match image::load_from_memory(&app_state.get_latest_image()) {
+        Ok(img) => img.to_rgb(),
+        Err(_) => {
+            open_error_dialog(app_state.clone(), "Couldn't read the image bytes");
+            return;
+        }
+    };
+
+    info!("Running every skeletonizer in parallel...");
+    let results = compare_algorithms(&image);
+
+    let grid = &app_state.compare_algorithms_grid;
+    for child in grid.get_children() {
+        grid.remove(&child);
+    }
+
+    for (index, (variant, result)) in SkeletonizerVariant::ALL.iter().zip(&results).enumerate() {
+        let cell = gtk::Box::new(Orientation::Vertical, 5);
+        match result {
+            Some(result) => {
+                if let Ok(pixbuf) = pixbuf_from_bytes(&result.bytes, 200) {
+                    cell.add(&Image::new_from_pixbuf(Some(&pixbuf)));
+                }
+
+                let text = format!(
+                    "{}\n{} pixels, {:.1} ms",
+                    variant.name(),
+                    result.pixels,
+                    result.elapsed.as_secs_f64() * 1000.0
+                );
+                cell.add(&Label::new(Some(text.as_str())));
+
+                let keep_button = Button::new_with_label("Keep");
+                let dialog = app_state.compare_algorithms_dialog.clone();
+                keep_button.connect_clicked(move |_| {
+                    dialog.response(ResponseType::Other(index as u16));
+                });
+                cell.add(&keep_button);
+            }
+            None => {
+                let text = format!("{}\nfailed", variant.name());
+                cell.add(&Label::new(Some(text.as_str())));
+            }
+        }
+
+        grid.attach(&cell, (index % 2) as i32, (index / 2) as i32, 1, 1);
+    }
+    grid.show_all();
+
+    if let ResponseType::Other(index) = app_state.compare_algorithms_dialog.run() {
+        if let Some(result) = &results[index as usize] {
+            info!("Keeping the result of {}", result.variant.name());
+            app_state.push_skeleton_snapshot(
+                format!("Skeletonized the image with the {} algorithm", result.variant.name()),
+                result.bytes.clone(),
+            );
+
+            update_image(app_state.clone());
+        }
+    }
+
+    app_state.compare_algorithms_dialog.hide();
+}
+
+// compares the latest snapshot with the image it was made from
+fn analyze_handler(app_state: Rc<AppState>) {
+    let previous_image = match app_state.get_previous_image() {
+        Some(bytes) if app_state.is_skeleton() => bytes,
+        _ => {
+            open_error_dialog(app_state, "Skeletonize the image first");
+            return;
+        }
+    };
+
+    info!("Loading images into memory...");
+    let images = (
+        image::load_from_memory(&previous_image),
+        image::load_from_memory(&app_state.get_latest_image()),
+    );
+    let (original, skeleton) = match images {
+        (Ok(original), Ok(skeleton)) => (BinaryGrid::from_image(&original), BinaryGrid::from_image(&skeleton)),
+        _ => {
+            open_error_dialog(app_state, "Couldn't read the image bytes");
+            return;
+        }
+    };
+
+    if (original.width(), original.height()) != (skeleton.width(), skeleton.height()) {
+        open_error_dialog(app_state, "The skeleton and the previous image differ in size");
+        return;
+    }
+
+    info!("Analyzing the skeleton...");
+    let analysis = imaging::analyze_skeleton(&original, &skeleton);
+
+    app_state.report_dialog.set_property_text(Some("Skeleton analysis"));
+    app_state.report_dialog.set_property_secondary_text(Some(analysis.to_string().as_str()));
+    app_state.report_dialog.run();
+    app_state.report_dialog.hide();
+}
+
+// labels the image and measures the components, the skeleton lengths come from Zhang-Suen
+fn measure_components(image: &DynamicImage, adjacency: AdjacencyMode) -> (Components, Vec<ComponentStats>) {
+    let grid = BinaryGrid::binarize(image);
+    let components = Components::label(&grid, adjacency);
+
+    let mut skeleton = BinaryImage::from_image(&image.to_rgb(), PixelColor::White);
+    ZhangSuenSkeletonizer::new().process(&mut skeleton);
+    let skeleton = BinaryGrid::from_image(&DynamicImage::ImageRgb8(skeleton.to_rgb_image()));
+
+    let stats = components.measure(&grid, &imaging::trace_graph(&skeleton));
+    (components, stats)
+}
+
+fn export_components(app_state: Rc<AppState>, stats: &[ComponentStats]) {
+    let file_chooser = FileChooserNative::new(Some("Export the components"),
+        Some(&app_state.main_window),
+        FileChooserAction::Save,
+        None,
+        None);
+    file_chooser.set_current_name("components.csv");
+
+    let csv = imaging::stats_to_csv(stats);
+    file_chooser.connect_response(move |chooser, response| {
+        if response == ResponseType::Accept {
+            save_image(app_state.clone(), chooser.get_filename().unwrap(), csv.as_bytes());
+        }
+    });
+
+    file_chooser.run();
+}
+
+fn components_handler(app_state: Rc<AppState>) {
+    let response = app_state.components_dialog.run();
+    app_state.components_dialog.hide();
+    if response != ResponseType::Ok && response != ResponseType::Other(1) {
+        return;
+    }
+
+    let adjacency = match app_state.components_adjacency_combo_box.get_active_id().as_deref() {
+        Some("Four") => AdjacencyMode::Four,
+        _ => AdjacencyMode::Eight,
+    };
+
+    info!("Loading image into memory...");
+    let image = match image::load_from_memory(&app_state.get_latest_image()) {
+        Ok(img) => img,
+        Err(_) => {
+            open_error_dialog(app_state.clone(), "Couldn't read the image bytes");
+            return;
+        }
+    };
+
+    info!("Measuring the components...");
+    let (components, stats) = measure_components(&image, adjacency);
+    if response == ResponseType::Other(1) {
+        export_components(app_state, &stats);
+        return;
+    }
+
+    let total_area: usize = stats.iter().map(|component| component.area).sum();
+    let largest = stats.iter().map(|component| component.area).max().unwrap_or(0);
+    let context_id = app_state.status_bar.get_context_id("components");
+    app_state.status_bar.remove_all(context_id);
+    app_state.status_bar.push(context_id, &format!(
+        "{} components, {} foreground pixels, the largest has {} pixels",
+        stats.len(), total_area, largest));
+
+    info!("Writing the result as byte array...");
+    match imaging::encode_png(&DynamicImage::ImageRgb8(components.to_false_color())) {
+        Ok(bytes) => {
+            app_state.push_snapshot(format!("Labelled {} components", components.count()), bytes);
+            update_image(app_state.clone());
+        }
+        Err(_) => open_error_dialog(app_state.clone(), "Couldn't write the image as PNG"),
+    }
+}
+
+fn invert_handler(app_state: Rc<AppState>) {
+    info!("Loading image into memory...");
+    let image = match image::load_from_memory(&app_state.get_latest_image()) {
+        Ok(img) => img,
+        Err(_) => {
+            open_error_dialog(app_state.clone(), "Couldn't read the image bytes");
+            return;
+        }
+    };
+
+    info!("Inverting the image...");
+    let region = selected_region(&app_state, &image);
+    let result = match region {
+        Some(region) => region.paste(&image, &imaging::invert(&region.crop(&image))),
+        None => imaging::invert(&image),
+    };
+
+    match imaging::encode_png(&result) {
+        Ok(bytes) => {
+            app_state.push_snapshot(describe_region("Inverted the image".to_string(), region), bytes);
+            update_image(app_state.clone());
+        }
+        Err(_) => open_error_dialog(app_state.clone(), "Couldn't write the image as PNG"),
+    }
+}
+
+fn geometry_handler(app_state: Rc<AppState>) {
+    let response = app_state.geometry_dialog.run();
+    app_state.geometry_dialog.hide();
+    if response != ResponseType::Ok {
+        return;
+    }
+
+    let operation = app_state.geometry_operation_combo_box.get_active_id().unwrap();
+    let angle = app_state.geometry_angle_spin_button.get_value();
+    let transform = match operation.as_str() {
+        "rotate-90" => GeometricTransform::Rotate(90.0),
+        "rotate-180" => GeometricTransform::Rotate(180.0),
+        "rotate-270" => GeometricTransform::Rotate(270.0),
+        "rotate" => GeometricTransform::Rotate(angle),
+        "flip-horizontal" => GeometricTransform::Flip(FlipDirection::Horizontal),
+        "flip-vertical" => GeometricTransform::Flip(FlipDirection::Vertical),
+        "deskew" => GeometricTransform::Deskew(angle.abs()),
+        _ => GeometricTransform::Resample {
+            dpi: app_state.geometry_dpi_spin_button.get_value(),
+            source_dpi: None,
+        },
+    };
+    let mut resolution = app_state.get_resolution();
+    if let (GeometricTransform::Resample { .. }, None) = (transform, resolution) {
+        open_error_dialog(app_state, "The image doesn't store its resolution, so it can't be resampled");
+        return;
+    }
+
+    info!("Loading image into memory...");
+    let image = match image::load_from_memory(&app_state.get_latest_image()) {
+        Ok(img) => img,
+        Err(_) => {
+            open_error_dialog(app_state.clone(), "Couldn't read the image bytes");
+            return;
+        }
+    };
+
+    info!("Transforming the image...");
+    let (result, description) = match transform {
+        GeometricTransform::Deskew(max_angle) => {
+            let (result, angle) = imaging::deskew(&image, max_angle);
+            (Ok(result), format!("Deskewed by rotating {:.2} degrees clockwise", angle))
+        }
+        _ => (transform.apply(&image, &mut resolution), transform.description()),
+    };
+    let result = match result {
+        Ok(result) => result,
+        Err(message) => {
+            open_error_dialog(app_state, message);
+            return;
+        }
+    };
+
+    info!("Writing the result as byte array...");
+    match imaging::encode_png(&result) {
+        Ok(bytes) => {
+            app_state.push_resampled_snapshot(description, bytes, resolution);
+            app_state.set_selection(None);
+            update_image(app_state.clone());
+        }
+        Err(_) => open_error_dialog(app_state.clone(), "Couldn't write the image as PNG"),
+    }
+}
+
+fn crop_handler(app_state: Rc<AppState>) {
+    info!("Loading image into memory...");
+    let image = match image::load_from_memory(&app_state.get_latest_image()) {
+        Ok(img) => img,
+        Err(_) => {
+            open_error_dialog(app_state.clone(), "Couldn't read the image bytes");
+            return;
+        }
+    };
+
+    let region = match selected_region(&app_state, &image) {
+        Some(region) => region,
+        None => {
+            open_error_dialog(app_state, "Drag over the image to select a region first");
+            return;
+        }
+    };
+
+    info!("Cropping the image...");
+    match imaging::encode_png(&region.crop(&image)) {
+        Ok(bytes) => {
+            app_state.push_snapshot(format!("Cropped to the region {}", region), bytes);
+            app_state.set_selection(None);
+            update_image(app_state.clone());
+        }
+        Err(_) => open_error_dialog(app_state.clone(), "Couldn't write the image as PNG"),
+    }
+}
+
+fn drawing_tool(app_state: &AppState) -> DrawingTool {
+    app_state.drawing_tool_combo_box
+        .get_active_id()
+        .map(|id| DrawingTool::from_id(&id))
+        .unwrap_or(DrawingTool::Select)
+}
+
+// every stroke becomes a snapshot so it can be undone, touching up
+// a skeleton keeps it a skeleton
+fn commit_drawing(app_state: Rc<AppState>, grid: &BinaryGrid, description: String) {
+    match imaging::encode_png(&DynamicImage::ImageRgb8(grid.to_rgb())) {
+        Ok(bytes) => {
+            info!("{}", description);
+            if app_state.is_skeleton() {
+                app_state.push_skeleton_snapshot(description, bytes);
+            } else {
+                app_state.push_snapshot(description, bytes);
+            }
+            update_image(app_state);
+        }
+        Err(_) => open_error_dialog(app_state, "Couldn't write the image as PNG"),
+    }
+}
+
+// the select tool drags a rubber band, the other tools draw with the left button
+// in black and with the right button in white
+fn pointer_pressed_handler(app_state: Rc<AppState>, position: (f64, f64), button: u32) {
+    let point = view_to_image(&app_state, position);
+    if app_state.is_picking_color() {
+        app_state.set_picking_color(false);
+        if let Some(point) = point {
+            pick_color(&app_state, point);
+        }
+        run_convert_to_binary_dialog(app_state);
+        return;
+    }
+
+    let tool = drawing_tool(&app_state);
+    if tool == DrawingTool::Select {
+        app_state.set_drag_start(point);
+        return;
+    }
+
+    let (point, display) = match (point, app_state.get_display_pixbuf()) {
+        (Some(point), Some(display)) => (point, display),
+        _ => return,
+    };
+    let image = match image::load_from_memory(&app_state.get_latest_image()) {
+        Ok(image) => image,
+        Err(_) => {
+            open_error_dialog(app_state.clone(), "Couldn't read the image bytes");
+            return;
+        }
+    };
+    // drawing on a grayscale or colour image would binarize it
+    if !imaging::is_binary(&image.to_rgb()) {
+        open_error_dialog(app_state, "The drawing tools edit binary images, convert the image to binary first");
+        return;
+    }
+    let mut grid = BinaryGrid::from_image(&image);
+
+    let value = button != 3;
+    if tool == DrawingTool::Fill {
+        let description = drawing::fill(&mut grid, point, value);
+        commit_drawing(app_state, &grid, description);
+        return;
+    }
+
+    let size = app_state.brush_size_spin_button.get_value_as_int().max(1) as u32;
+    let stroke = Stroke::begin(tool, value, size, grid, point, &display);
+    if let Some(stroke) = &stroke {
+        app_state.image_view.set_from_pixbuf(Some(stroke.preview()));
+    }
+    *app_state.get_stroke() = stroke;
+}
+
+fn pointer_moved_handler(app_state: Rc<AppState>, position: (f64, f64)) {
+    show_inspector(&app_state, Some(position));
+
+    let point = match view_to_image(&app_state, position) {
+        Some(point) => point,
+        None => return,
+    };
+
+    if let Some(stroke) = app_state.get_stroke().as_mut() {
+        stroke.extend(point);
+        app_state.image_view.set_from_pixbuf(Some(stroke.preview()));
+    } else if let Some(start) = app_state.get_drag_start() {
+        app_state.set_selection(Some(Roi::from_corners(start, point)));
+        show_selection(&app_state);
+    }
+}
+
+// a click without dragging clears the selection
+fn pointer_released_handler(app_state: Rc<AppState>, position: (f64, f64)) {
+    let stroke = app_state.get_stroke().take();
+    if let Some(stroke) = stroke {
+        let (grid, description) = stroke.finish();
+        commit_drawing(app_state, &grid, description);
+    } else if let Some(start) = app_state.get_drag_start() {
+        let end = view_to_image(&app_state, position).unwrap_or(start);
+        app_state.set_selection(if start == end {
+            None
+        } else {
+            Some(Roi::from_corners(start, end))
+        });
+        app_state.set_drag_start(None);
+        show_selection(&app_state);
+    }
+}
+
 fn undo_handler(app_state: Rc<AppState>) {
     match app_state.pop_snapshot() {
-        Some(_) => {
-            info!("Undo");
+        Some(description) => {
+            info!("Undo: {}", description);
             update_image(app_state);
         }, 
         None => info!("Nothing to undo")
     };
 }
 
+fn redo_handler(app_state: Rc<AppState>) {
+    match app_state.redo_snapshot() {
+        Some(description) => {
+            info!("Redo: {}", description);
+            update_image(app_state);
+        },
+        None => info!("Nothing to redo")
+    };
+}
+
+fn zoom_handler(app_state: Rc<AppState>, zoom: f64) {
+    let zoom = zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+    info!("Zooming to {:.0}%", zoom * 100.0);
+    app_state.set_zoom(zoom);
+    if image_dimensions(&app_state).is_some() {
+        update_image(app_state);
+    }
+}
+
+// opening a file from the menu works like choosing it with the file chooser button
+fn open_handler(app_state: Rc<AppState>) {
+    let file_chooser = FileChooserNative::new(Some("Open an image"),
+        Some(&app_state.main_window),
+        FileChooserAction::Open,
+        None,
+        None);
+
+    file_chooser.connect_response(move |chooser, response| {
+        if response == ResponseType::Accept {
+            if let Some(path) = chooser.get_filename() {
+                app_state.file_chooser_button.set_filename(&path);
+                file_set_handler(app_state.clone());
+            }
+        }
+    });
+
+    file_chooser.run();
+}
+
+fn page_changed_handler(app_state: Rc<AppState>) {
+    let page = app_state.page_spin_button.get_value_as_int() as usize;
+    info!("Switching to page {}", page);
+    app_state.set_current_page(page - 1);
+    update_image(app_state);
+}
+
+// the skeleton is traced and saved as polylines
+fn save_vector(app_state: Rc<AppState>, path: &Path, format: VectorFormat) {
+    info!("Tracing the skeleton into polylines...");
+    let image = match image::load_from_memory(&app_state.get_latest_image()) {
+        Ok(image) => image,
+        Err(_) => {
+            open_error_dialog(app_state, "Couldn't read the image bytes");
+            return;
+        }
+    };
+
+    let graph = imaging::trace_graph(&BinaryGrid::from_image(&image));
+    let options = VectorOptions {
+        dpi: app_state.get_metadata().dpi.map(|(x, _)| x),
+        ..VectorOptions::default()
+    };
+    let text = imaging::encode_vector(&graph, format, &options);
+    save_image(app_state, path, text.as_bytes());
+}
+
 fn save_handler(app_state: Rc<AppState>) {
     let file_chooser = FileChooserNative::new(Some("Save the image"),
         Some(&app_state.main_window),
@@ -249,10 +1238,31 @@ fn save_handler(app_state: Rc<AppState>) {
         None,
         None);
 
+    for &(name, pattern) in &[("PNG image", "*.png"), ("SVG drawing", "*.svg"), ("DXF drawing", "*.dxf")] {
+        let filter = FileFilter::new();
+        filter.set_name(Some(name));
+        filter.add_pattern(pattern);
+        file_chooser.add_filter(&filter);
+    }
+
     file_chooser.connect_response(move |chooser, response| {
         match response {
             ResponseType::Accept => {
-                save_image(app_state.clone(), chooser.get_filename().unwrap());
+                let path = chooser.get_filename().unwrap();
+                let vector_format = path
+                    .extension()
+                    .and_then(|ext| VectorFormat::from_name(&ext.to_string_lossy()));
+
+                match vector_format {
+                    Some(format) => save_vector(app_state.clone(), &path, format),
+                    None => {
+                        let bytes = imaging::embed_metadata(
+                            app_state.get_latest_image().clone(),
+                            ImageFormat::Png,
+                            &app_state.get_metadata());
+                        save_image(app_state.clone(), path, &bytes);
+                    }
+                }
             },
             _ => ()
         };
@@ -261,9 +1271,98 @@ fn save_handler(app_state: Rc<AppState>) {
     file_chooser.run();
 }
 
+// all pages are written into a single multi-page TIFF
+fn save_all_pages_handler(app_state: Rc<AppState>) {
+    let file_chooser = FileChooserNative::new(Some("Save all pages"),
+        Some(&app_state.main_window),
+        FileChooserAction::Save,
+        None,
+        None);
+    file_chooser.set_current_name("pages.tiff");
+
+    file_chooser.connect_response(move |chooser, response| {
+        match response {
+            ResponseType::Accept => {
+                info!("Loading the pages into memory...");
+                let pages = match app_state
+                    .get_latest_images()
+                    .iter()
+                    .map(|bytes| image::load_from_memory(bytes))
+                    .collect::<Result<Vec<_>, _>>() {
+                    Ok(pages) => pages,
+                    Err(_) => {
+                        open_error_dialog(app_state.clone(), "Couldn't read the image bytes");
+                        return;
+                    }
+                };
+
+                info!("Writing the pages as TIFF...");
+                let bytes = match imaging::encode_tiff_pages(&pages, &app_state.get_document_metadata()) {
+                    Ok(bytes) => bytes,
+                    Err(_) => {
+                        open_error_dialog(app_state.clone(), "Couldn't write the pages as TIFF");
+                        return;
+                    }
+                };
+
+                save_image(app_state.clone(), chooser.get_filename().unwrap(), &bytes);
+            },
+            _ => ()
+        };
+    });
+
+    file_chooser.run();
+}
+
+// registers an application action with its keyboard accelerators
+fn register_action<F>(
+    application: &gtk::Application,
+    app_state: Rc<AppState>,
+    name: &str,
+    accels: &[&str],
+    handler: F,
+) where
+    F: Fn(Rc<AppState>) + 'static,
+{
+    let action = SimpleAction::new(name, None);
+    action.connect_activate(move |_, _| {
+        handler(app_state.clone());
+    });
+    application.add_action(&action);
+    application.set_accels_for_action(&format!("app.{}", name), accels);
+}
+
 // connect signals, show ui
 fn build_ui(application: &gtk::Application, app_state: Rc<AppState>) {
     app_state.main_window.set_application(Some(application));
+    app_state.main_window.set_help_overlay(Some(&app_state.shortcuts_window));
+    application.set_accels_for_action("win.show-help-overlay", &["<Primary>question", "<Primary>F1"]);
+
+    // the buttons of these operations and the header bar menu activate the same actions
+    register_action(application, app_state.clone(), "open", &["<Primary>o"], open_handler);
+    register_action(application, app_state.clone(), "save", &["<Primary>s"], save_handler);
+    register_action(application, app_state.clone(), "undo", &["<Primary>z"], undo_handler);
+    register_action(application, app_state.clone(), "redo", &["<Primary><Shift>z", "<Primary>y"], redo_handler);
+    register_action(application, app_state.clone(), "binarize", &["<Primary>b"], convert_to_binary_handler);
+    register_action(application, app_state.clone(), "skeletonize", &["<Primary>k"], skeletonize_handler);
+    register_action(application, app_state.clone(), "zoom-in", &["<Primary>plus", "<Primary>equal"], |app_state| {
+        let zoom = app_state.get_zoom() * ZOOM_STEP;
+        zoom_handler(app_state, zoom);
+    });
+    register_action(application, app_state.clone(), "zoom-out", &["<Primary>minus"], |app_state| {
+        let zoom = app_state.get_zoom() / ZOOM_STEP;
+        zoom_handler(app_state, zoom);
+    });
+    register_action(application, app_state.clone(), "zoom-reset", &["<Primary>0"], |app_state| {
+        zoom_handler(app_state, 1.0);
+    });
+    register_action(application, app_state.clone(), "quit", &["<Primary>q"], |app_state| {
+        app_state.main_window.close();
+    });
+    app_state.convert_to_binary_button.set_action_name(Some("app.binarize"));
+    app_state.skeletonize_button.set_action_name(Some("app.skeletonize"));
+    app_state.undo_button.set_action_name(Some("app.undo"));
+    app_state.save_button.set_action_name(Some("app.save"));
 
     let app_state_cloned = app_state.clone();
     app_state.file_chooser_button.connect_file_set(move |_| {
@@ -271,25 +1370,97 @@ fn build_ui(application: &gtk::Application, app_state: Rc<AppState>) {
     });
 
     let app_state_cloned = app_state.clone();
-    app_state
-        .convert_to_binary_button
-        .connect_clicked(move |_| {
-            convert_to_binary_handler(app_state_cloned.clone());
-        });
+    app_state.prune_button.connect_clicked(move |_| {
+        prune_handler(app_state_cloned.clone());
+    });
 
     let app_state_cloned = app_state.clone();
-    app_state.skeletonize_button.connect_clicked(move |_| {
-        skeletonize_handler(app_state_cloned.clone());
+    app_state.morphology_button.connect_clicked(move |_| {
+        morphology_handler(app_state_cloned.clone());
     });
-    
+
+    let app_state_cloned = app_state.clone();
+    app_state.invert_button.connect_clicked(move |_| {
+        invert_handler(app_state_cloned.clone());
+    });
+
+    let app_state_cloned = app_state.clone();
+    app_state.preprocess_button.connect_clicked(move |_| {
+        preprocess_handler(app_state_cloned.clone());
+    });
+
+    let app_state_cloned = app_state.clone();
+    app_state.crop_button.connect_clicked(move |_| {
+        crop_handler(app_state_cloned.clone());
+    });
+
+    let app_state_cloned = app_state.clone();
+    app_state.geometry_button.connect_clicked(move |_| {
+        geometry_handler(app_state_cloned.clone());
+    });
+
+    let app_state_cloned = app_state.clone();
+    app_state.image_event_box.connect_button_press_event(move |_, event| {
+        pointer_pressed_handler(app_state_cloned.clone(), event.get_position(), event.get_button());
+        Inhibit(false)
+    });
+
+    let app_state_cloned = app_state.clone();
+    app_state.image_event_box.connect_motion_notify_event(move |_, event| {
+        pointer_moved_handler(app_state_cloned.clone(), event.get_position());
+        Inhibit(false)
+    });
+
+    let app_state_cloned = app_state.clone();
+    app_state.image_event_box.connect_leave_notify_event(move |_, _| {
+        show_inspector(&app_state_cloned, None);
+        Inhibit(false)
+    });
+
+    let app_state_cloned = app_state.clone();
+    app_state.image_event_box.connect_button_release_event(move |_, event| {
+        pointer_released_handler(app_state_cloned.clone(), event.get_position());
+        Inhibit(false)
+    });
+
+    let app_state_cloned = app_state.clone();
+    app_state.components_button.connect_clicked(move |_| {
+        components_handler(app_state_cloned.clone());
+    });
+
+    let app_state_cloned = app_state.clone();
+    app_state.compare_algorithms_button.connect_clicked(move |_| {
+        compare_algorithms_handler(app_state_cloned.clone());
+    });
+
+    let app_state_cloned = app_state.clone();
+    app_state.analyze_button.connect_clicked(move |_| {
+        analyze_handler(app_state_cloned.clone());
+    });
+
+    let app_state_cloned = app_state.clone();
+    app_state.show_graph_check_button.connect_toggled(move |_| {
+        update_image(app_state_cloned.clone());
+    });
+
+    let app_state_cloned = app_state.clone();
+    app_state.show_medial_axis_check_button.connect_toggled(move |_| {
+        update_image(app_state_cloned.clone());
+    });
+
+    let app_state_cloned = app_state.clone();
+    app_state.show_contours_check_button.connect_toggled(move |_| {
+        update_image(app_state_cloned.clone());
+    });
+
     let app_state_cloned = app_state.clone();
-    app_state.undo_button.connect_clicked(move |_| {
-        undo_handler(app_state_cloned.clone());
+    app_state.save_all_pages_button.connect_clicked(move |_| {
+        save_all_pages_handler(app_state_cloned.clone());
     });
 
     let app_state_cloned = app_state.clone();
-    app_state.save_button.connect_clicked(move |_| {
-        save_handler(app_state_cloned.clone());
+    app_state.page_spin_button.connect_value_changed(move |_| {
+        page_changed_handler(app_state_cloned.clone());
     });
 
     app_state.main_window.show_all();
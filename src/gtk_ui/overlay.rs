@@ -0,0 +1,123 @@
+// overlay.rs - Draws analysis results over the displayed image
+// Copyright (C) 2019 Denis Karpovskiy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use gdk_pixbuf::Pixbuf;
+use image::{Rgb, RgbImage};
+use crate::imaging::{ContourKind, Contours, MedialAxis, NodeKind, Roi, SkeletonGraph};
+
+const ENDPOINT_COLOR: Rgb<u8> = Rgb([220, 20, 20]);
+const JUNCTION_COLOR: Rgb<u8> = Rgb([20, 90, 230]);
+const OUTER_CONTOUR_COLOR: Rgb<u8> = Rgb([20, 160, 60]);
+const HOLE_CONTOUR_COLOR: Rgb<u8> = Rgb([240, 140, 0]);
+const SELECTION_COLOR: Rgb<u8> = Rgb([230, 0, 200]);
+
+/// The overlays of the latest image, each computed when it's first shown,
+/// so zooming and toggling them doesn't compute them again
+#[derive(Default)]
+pub struct OverlayCache {
+    pub medial_axis: Option<MedialAxis>,
+    pub contours: Option<Contours>,
+    pub graph: Option<SkeletonGraph>,
+    /// The image with the overlays drawn over it, with the graph, medial
+    /// axis and contours check buttons it was drawn for
+    pub rendered: Option<((bool, bool, bool), Vec<u8>)>,
+}
+
+// the image is scaled down to this height when displayed
+const DISPLAY_HEIGHT: u32 = 400;
+
+// markers have to survive the scaling, so they grow with the image
+fn marker_radius(image: &RgbImage) -> i64 {
+    (image.height() / DISPLAY_HEIGHT).max(1) as i64
+}
+
+fn draw_marker(image: &mut RgbImage, x: f64, y: f64, radius: i64, color: Rgb<u8>) {
+    let (cx, cy) = (x.round() as i64, y.round() as i64);
+    for py in cy - radius..=cy + radius {
+        for px in cx - radius..=cx + radius {
+            if px >= 0 && py >= 0 && (px as u32) < image.width() && (py as u32) < image.height() {
+                image.put_pixel(px as u32, py as u32, color);
+            }
+        }
+    }
+}
+
+pub fn draw_graph(image: &mut RgbImage, graph: &SkeletonGraph) {
+    let radius = marker_radius(image);
+    for node in &graph.nodes {
+        let color = match node.kind {
+            NodeKind::Endpoint | NodeKind::Isolated => ENDPOINT_COLOR,
+            NodeKind::Junction => JUNCTION_COLOR,
+            NodeKind::Loop => continue
+        };
+        draw_marker(image, node.x, node.y, radius, color);
+    }
+}
+
+// blue for the thinnest strokes through green to red for the thickest
+fn radius_color(fraction: f64) -> Rgb<u8> {
+    let fraction = fraction.clamp(0.0, 1.0);
+    let red = (2.0 * fraction - 1.0).max(0.0);
+    let blue = (1.0 - 2.0 * fraction).max(0.0);
+    let green = 1.0 - red - blue;
+    Rgb([(red * 255.0) as u8, (green * 255.0) as u8, (blue * 255.0) as u8])
+}
+
+pub fn draw_medial_axis(image: &mut RgbImage, axis: &MedialAxis) {
+    let radius = marker_radius(image) - 1;
+    let max_radius = axis.max_radius();
+    for point in &axis.points {
+        let fraction = if max_radius > 1.0 { (point.radius - 1.0) / (max_radius - 1.0) } else { 0.0 };
+        draw_marker(image, point.x as f64, point.y as f64, radius, radius_color(fraction));
+    }
+}
+
+pub fn draw_contours(image: &mut RgbImage, contours: &Contours) {
+    let radius = marker_radius(image) - 1;
+    for contour in &contours.contours {
+        let color = match contour.kind {
+            ContourKind::Outer => OUTER_CONTOUR_COLOR,
+            ContourKind::Hole => HOLE_CONTOUR_COLOR
+        };
+        for &(x, y) in &contour.points {
+            draw_marker(image, x as f64, y as f64, radius, color);
+        }
+    }
+}
+
+// the outline of the selection on the displayed pixbuf, `scale` maps image pixels to it
+pub fn draw_selection(pixbuf: &Pixbuf, selection: Roi, scale: f64) {
+    let (width, height) = (pixbuf.get_width() as u32, pixbuf.get_height() as u32);
+    if width == 0 || height == 0 {
+        return;
+    }
+
+    let to_view = |value: u32, limit: u32| ((value as f64 * scale) as u32).min(limit - 1);
+    let (left, top) = (to_view(selection.x, width), to_view(selection.y, height));
+    let right = to_view(selection.x + selection.width - 1, width);
+    let bottom = to_view(selection.y + selection.height - 1, height);
+
+    let Rgb([red, green, blue]) = SELECTION_COLOR;
+    let put = |x: u32, y: u32| pixbuf.put_pixel(x as _, y as _, red, green, blue, 255);
+    for x in left..=right {
+        put(x, top);
+        put(x, bottom);
+    }
+    for y in top..=bottom {
+        put(left, y);
+        put(right, y);
+    }
+}
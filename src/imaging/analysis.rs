@@ -0,0 +1,178 @@
+// analysis.rs - Checks the quality of a skeleton against its source image
+// Copyright (C) 2019 Denis Karpovskiy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use improc_petrsu::AdjacencyMode;
+use serde::Serialize;
+use std::fmt;
+use super::binary::BinaryGrid;
+use super::components::{ self, Components };
+use super::graph::{ self, PixelKind };
+
+/// How many deviations the text report lists
+const LISTED_DEVIATIONS: usize = 10;
+
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeviationKind {
+    /// A 2x2 block of skeleton pixels, the skeleton isn't one pixel thick
+    Thick,
+    /// A skeleton pixel outside the source shape
+    OutsideShape,
+    /// A component of the source image without any skeleton pixels
+    LostComponent
+}
+
+impl DeviationKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DeviationKind::Thick => "thick",
+            DeviationKind::OutsideShape => "outside the shape",
+            DeviationKind::LostComponent => "lost component"
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Deviation {
+    pub kind: DeviationKind,
+    pub x: u32,
+    pub y: u32
+}
+
+#[derive(Debug, Serialize)]
+pub struct SkeletonAnalysis {
+    pub thickness_violations: usize,
+    pub components_before: usize,
+    pub components_after: usize,
+    pub holes_before: usize,
+    pub holes_after: usize,
+    pub euler_before: i64,
+    pub euler_after: i64,
+    pub topology_preserved: bool,
+    pub endpoints: usize,
+    pub junctions: usize,
+    pub deviations: Vec<Deviation>
+}
+
+/// Compares the skeleton with the binary image it was made from. Both grids
+/// must have the same size. Components are 8-connected, holes 4-connected.
+pub fn analyze_skeleton(original: &BinaryGrid, skeleton: &BinaryGrid) -> SkeletonAnalysis {
+    let mut deviations = Vec::new();
+
+    for y in 0..skeleton.height().saturating_sub(1) {
+        for x in 0..skeleton.width().saturating_sub(1) {
+            if skeleton.at(x, y) && skeleton.at(x + 1, y) && skeleton.at(x, y + 1) && skeleton.at(x + 1, y + 1) {
+                deviations.push(Deviation { kind: DeviationKind::Thick, x, y });
+            }
+        }
+    }
+    let thickness_violations = deviations.len();
+
+    let original_components = Components::label(original, AdjacencyMode::Eight);
+    let mut skeletonized = vec![false; original_components.count() + 1];
+    let mut endpoints = 0;
+    // touching junction pixels are one junction, like the nodes of `trace_graph`
+    let mut junction_pixels = BinaryGrid::new(skeleton.width(), skeleton.height());
+
+    for (x, y) in skeleton.foreground() {
+        if !original.at(x, y) {
+            deviations.push(Deviation { kind: DeviationKind::OutsideShape, x, y });
+        }
+        skeletonized[original_components.label_at(x, y) as usize] = true;
+
+        match graph::classify_pixel(skeleton, x, y) {
+            PixelKind::Endpoint => endpoints += 1,
+            PixelKind::Junction => junction_pixels.set(x, y, true),
+            _ => {}
+        }
+    }
+
+    for (x, y) in original.foreground() {
+        let label = original_components.label_at(x, y) as usize;
+        if !skeletonized[label] {
+            // report every lost component once, at its first pixel
+            skeletonized[label] = true;
+            deviations.push(Deviation { kind: DeviationKind::LostComponent, x, y });
+        }
+    }
+
+    let junctions = Components::label(&junction_pixels, AdjacencyMode::Eight).count();
+    let components_before = original_components.count();
+    let components_after = Components::label(skeleton, AdjacencyMode::Eight).count();
+    let holes_before = components::count_holes(original, AdjacencyMode::Eight);
+    let holes_after = components::count_holes(skeleton, AdjacencyMode::Eight);
+    let euler_before = components_before as i64 - holes_before as i64;
+    let euler_after = components_after as i64 - holes_after as i64;
+
+    SkeletonAnalysis {
+        thickness_violations,
+        components_before,
+        components_after,
+        holes_before,
+        holes_after,
+        euler_before,
+        euler_after,
+        topology_preserved: components_before == components_after && holes_before == holes_after,
+        endpoints,
+        junctions,
+        deviations
+    }
+}
+
+impl fmt::Display for SkeletonAnalysis {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Thickness violations: {}", self.thickness_violations)?;
+        writeln!(f, "Components: {} -> {}", self.components_before, self.components_after)?;
+        writeln!(f, "Holes: {} -> {}", self.holes_before, self.holes_after)?;
+        writeln!(f, "Euler number: {} -> {} ({})", self.euler_before, self.euler_after,
+            if self.topology_preserved { "topology preserved" } else { "topology changed" })?;
+        writeln!(f, "Endpoints: {}", self.endpoints)?;
+        writeln!(f, "Junctions: {}", self.junctions)?;
+        write!(f, "Deviations: {}", self.deviations.len())?;
+
+        for deviation in self.deviations.iter().take(LISTED_DEVIATIONS) {
+            write!(f, "\n    {} at ({}, {})", deviation.kind.name(), deviation.x, deviation.y)?;
+        }
+        if self.deviations.len() > LISTED_DEVIATIONS {
+            write!(f, "\n    and {} more", self.deviations.len() - LISTED_DEVIATIONS)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn touching_junction_pixels_are_one_junction() {
+        // a horizontal line with a branch going up from one pixel and a
+        // branch going down from the next one
+        let mut skeleton = BinaryGrid::new(7, 7);
+        for offset in 0..7 {
+            skeleton.set(offset, 3, true);
+        }
+        for offset in 0..3 {
+            skeleton.set(3, offset, true);
+            skeleton.set(4, offset + 4, true);
+        }
+
+        let analysis = analyze_skeleton(&skeleton, &skeleton);
+        assert_eq!(analysis.junctions, 1);
+        assert_eq!(analysis.endpoints, 4);
+    }
+}
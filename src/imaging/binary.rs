@@ -0,0 +1,151 @@
+// binary.rs - Helpers for black-and-white images
+// Copyright (C) 2019 Denis Karpovskiy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use image::{ DynamicImage, Rgb, RgbImage };
+use improc_petrsu::{ BinaryImage, PixelColor };
+
+/// Pixels darker than this are the foreground (the strokes of a drawing)
+pub const FOREGROUND_THRESHOLD: u8 = 128;
+
+/// Counts the foreground pixels of the image
+pub fn count_foreground(image: &DynamicImage) -> u64 {
+    image
+        .to_luma()
+        .pixels()
+        .filter(|pixel| pixel[0] < FOREGROUND_THRESHOLD)
+        .count() as u64
+}
+
+/// Whether the image holds only black and white pixels, like the results of thresholding
+pub fn is_binary(image: &RgbImage) -> bool {
+    image.pixels().all(|pixel| pixel.0 == [0, 0, 0] || pixel.0 == [255, 255, 255])
+}
+
+/// Offsets of the 8 neighbours, clockwise starting from the north
+pub const NEIGHBOURS: [(i64, i64); 8] = [
+    (0, -1), (1, -1), (1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1)
+];
+
+/// A black-and-white raster where `true` marks the foreground
+#[derive(Clone, Debug, PartialEq)]
+pub struct BinaryGrid {
+    width: u32,
+    height: u32,
+    pixels: Vec<bool>
+}
+
+impl BinaryGrid {
+    pub fn new(width: u32, height: u32) -> Self {
+        BinaryGrid {
+            width,
+            height,
+            pixels: vec![false; width as usize * height as usize]
+        }
+    }
+
+    pub fn from_image(image: &DynamicImage) -> Self {
+        let gray = image.to_luma();
+        BinaryGrid {
+            width: gray.width(),
+            height: gray.height(),
+            pixels: gray.pixels().map(|pixel| pixel[0] < FOREGROUND_THRESHOLD).collect()
+        }
+    }
+
+    /// Binarizes the image with `BinaryImage::from_image` of the library
+    pub fn binarize(image: &DynamicImage) -> Self {
+        let binary_image = BinaryImage::from_image(&image.to_rgb(), PixelColor::White);
+        Self::from_image(&DynamicImage::ImageRgb8(binary_image.to_rgb_image()))
+    }
+
+    /// Renders the foreground black on white, like the library does
+    pub fn to_rgb(&self) -> RgbImage {
+        RgbImage::from_fn(self.width, self.height, |x, y| if self.at(x, y) {
+            Rgb([0, 0, 0])
+        } else {
+            Rgb([255, 255, 255])
+        })
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn index(&self, x: u32, y: u32) -> usize {
+        y as usize * self.width as usize + x as usize
+    }
+
+    pub fn at(&self, x: u32, y: u32) -> bool {
+        self.pixels[self.index(x, y)]
+    }
+
+    /// Like `at`, but everything outside the grid is background
+    pub fn get(&self, x: i64, y: i64) -> bool {
+        x >= 0 && y >= 0 && x < self.width as i64 && y < self.height as i64
+            && self.at(x as u32, y as u32)
+    }
+
+    pub fn set(&mut self, x: u32, y: u32, value: bool) {
+        let index = self.index(x, y);
+        self.pixels[index] = value;
+    }
+
+    pub fn count(&self) -> u64 {
+        self.pixels.iter().filter(|&&pixel| pixel).count() as u64
+    }
+
+    /// Coordinates of the foreground pixels in row order
+    pub fn foreground(&self) -> impl Iterator<Item = (u32, u32)> + '_ {
+        let width = self.width;
+        self.pixels
+            .iter()
+            .enumerate()
+            .filter(|(_, &pixel)| pixel)
+            .map(move |(index, _)| ((index % width as usize) as u32, (index / width as usize) as u32))
+    }
+
+    /// The 8 neighbours of the pixel in the order of `NEIGHBOURS`
+    pub fn neighbours(&self, x: u32, y: u32) -> [bool; 8] {
+        let mut neighbours = [false; 8];
+        for (neighbour, &(dx, dy)) in neighbours.iter_mut().zip(NEIGHBOURS.iter()) {
+            *neighbour = self.get(x as i64 + dx, y as i64 + dy);
+        }
+        neighbours
+    }
+
+    pub fn neighbour_count(&self, x: u32, y: u32) -> usize {
+        self.neighbours(x, y).iter().filter(|&&neighbour| neighbour).count()
+    }
+
+    /// The number of background to foreground transitions going once around
+    /// the neighbours, the count of separate runs of foreground neighbours
+    pub fn crossing_number(&self, x: u32, y: u32) -> usize {
+        let neighbours = self.neighbours(x, y);
+        (0..8).filter(|&index| !neighbours[index] && neighbours[(index + 1) % 8]).count()
+    }
+
+    pub fn inverted(&self) -> Self {
+        BinaryGrid {
+            width: self.width,
+            height: self.height,
+            pixels: self.pixels.iter().map(|&pixel| !pixel).collect()
+        }
+    }
+}
@@ -0,0 +1,41 @@
+// color.rs - Parses colours given on the command line and inverts images
+// Copyright (C) 2019 Denis Karpovskiy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use image::{ DynamicImage, Rgb };
+
+/// Parses "white", "black" or a hex colour like "#ff8800"
+pub fn parse_color(s: &str) -> Result<Rgb<u8>, String> {
+    match s.trim().to_lowercase().as_str() {
+        "white" => Ok(Rgb([255, 255, 255])),
+        "black" => Ok(Rgb([0, 0, 0])),
+        hex => {
+            let hex = hex.trim_start_matches('#');
+            let value = match u32::from_str_radix(hex, 16) {
+                Ok(value) if hex.len() == 6 => value,
+                _ => return Err(format!("Invalid colour: '{}'", s))
+            };
+            Ok(Rgb([(value >> 16) as u8, (value >> 8) as u8, value as u8]))
+        }
+    }
+}
+
+/// Swaps the foreground and the background. The colour channels become
+/// 255 minus their value and the alpha stays, so binary images stay exact.
+pub fn invert(image: &DynamicImage) -> DynamicImage {
+    let mut result = image.clone();
+    result.invert();
+    result
+}
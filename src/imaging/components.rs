@@ -0,0 +1,240 @@
+// components.rs - Labels connected components of binary images
+// Copyright (C) 2019 Denis Karpovskiy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use image::{ Rgb, RgbImage };
+use improc_petrsu::AdjacencyMode;
+use serde::Serialize;
+use super::binary::{ BinaryGrid, NEIGHBOURS };
+use super::graph::SkeletonGraph;
+
+const FOUR_NEIGHBOURS: [(i64, i64); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+
+pub fn neighbour_offsets(adjacency: AdjacencyMode) -> &'static [(i64, i64)] {
+    match adjacency {
+        AdjacencyMode::Four => &FOUR_NEIGHBOURS,
+        AdjacencyMode::Eight => &NEIGHBOURS
+    }
+}
+
+/// The adjacency the background needs so that the topology stays consistent
+pub fn complementary(adjacency: AdjacencyMode) -> AdjacencyMode {
+    match adjacency {
+        AdjacencyMode::Four => AdjacencyMode::Eight,
+        AdjacencyMode::Eight => AdjacencyMode::Four
+    }
+}
+
+/// Measurements of a single component, the coordinates are in pixels
+#[derive(Clone, Debug, Serialize)]
+pub struct ComponentStats {
+    pub label: u32,
+    pub area: usize,
+    pub min_x: u32,
+    pub min_y: u32,
+    pub max_x: u32,
+    pub max_y: u32,
+    pub centroid_x: f64,
+    pub centroid_y: f64,
+    /// Pixel edges shared with the background or the border of the image
+    pub perimeter: usize,
+    /// Total length of the skeleton lines inside the component
+    pub skeleton_length: f64
+}
+
+// evenly spread hues that stay distinct for neighbouring labels
+fn label_color(label: u32) -> Rgb<u8> {
+    let hue = (label as f64 * 0.618_033_988_75).fract() * 6.0;
+    let (value, saturation) = (0.9, 0.7);
+    let chroma = value * saturation;
+    let x = chroma * (1.0 - (hue % 2.0 - 1.0).abs());
+    let (r, g, b) = match hue as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x)
+    };
+    let m = value - chroma;
+    Rgb([((r + m) * 255.0) as u8, ((g + m) * 255.0) as u8, ((b + m) * 255.0) as u8])
+}
+
+/// Connected components of the foreground of a binary image
+pub struct Components {
+    width: u32,
+    /// 0 for the background, 1 to `count` for the components
+    labels: Vec<u32>,
+    count: usize
+}
+
+impl Components {
+    pub fn label(grid: &BinaryGrid, adjacency: AdjacencyMode) -> Self {
+        let offsets = neighbour_offsets(adjacency);
+        let mut labels = vec![0; grid.width() as usize * grid.height() as usize];
+        let mut count = 0;
+        let mut stack = Vec::new();
+
+        for (x, y) in grid.foreground() {
+            if labels[grid.index(x, y)] != 0 {
+                continue;
+            }
+
+            count += 1;
+            labels[grid.index(x, y)] = count as u32;
+            stack.push((x, y));
+
+            while let Some((x, y)) = stack.pop() {
+                for &(dx, dy) in offsets {
+                    let (nx, ny) = (x as i64 + dx, y as i64 + dy);
+                    if !grid.get(nx, ny) {
+                        continue;
+                    }
+
+                    let index = grid.index(nx as u32, ny as u32);
+                    if labels[index] == 0 {
+                        labels[index] = count as u32;
+                        stack.push((nx as u32, ny as u32));
+                    }
+                }
+            }
+        }
+
+        Components {
+            width: grid.width(),
+            labels,
+            count
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn label_at(&self, x: u32, y: u32) -> u32 {
+        self.labels[y as usize * self.width as usize + x as usize]
+    }
+
+    /// Pixel counts indexed by label, the background is at 0
+    pub fn areas(&self) -> Vec<usize> {
+        let mut areas = vec![0; self.count + 1];
+        for &label in &self.labels {
+            areas[label as usize] += 1;
+        }
+        areas
+    }
+
+    pub fn height(&self) -> u32 {
+        if self.width == 0 { 0 } else { (self.labels.len() / self.width as usize) as u32 }
+    }
+
+    /// Every component in its own colour on a white background
+    pub fn to_false_color(&self) -> RgbImage {
+        RgbImage::from_fn(self.width, self.height(), |x, y| match self.label_at(x, y) {
+            0 => Rgb([255, 255, 255]),
+            label => label_color(label)
+        })
+    }
+
+    /// Measures every component of the labelled grid, the skeleton length is the sum
+    /// of the graph edges that start inside the component
+    pub fn measure(&self, grid: &BinaryGrid, skeleton: &SkeletonGraph) -> Vec<ComponentStats> {
+        let mut stats: Vec<ComponentStats> = (1..=self.count as u32)
+            .map(|label| ComponentStats {
+                label,
+                area: 0,
+                min_x: u32::MAX,
+                min_y: u32::MAX,
+                max_x: 0,
+                max_y: 0,
+                centroid_x: 0.0,
+                centroid_y: 0.0,
+                perimeter: 0,
+                skeleton_length: 0.0
+            })
+            .collect();
+
+        for (x, y) in grid.foreground() {
+            let component = &mut stats[self.label_at(x, y) as usize - 1];
+            component.area += 1;
+            component.min_x = component.min_x.min(x);
+            component.min_y = component.min_y.min(y);
+            component.max_x = component.max_x.max(x);
+            component.max_y = component.max_y.max(y);
+            component.centroid_x += x as f64;
+            component.centroid_y += y as f64;
+            component.perimeter += FOUR_NEIGHBOURS
+                .iter()
+                .filter(|&&(dx, dy)| !grid.get(x as i64 + dx, y as i64 + dy))
+                .count();
+        }
+
+        for component in &mut stats {
+            component.centroid_x /= component.area as f64;
+            component.centroid_y /= component.area as f64;
+        }
+
+        for edge in &skeleton.edges {
+            let (x, y) = edge.points[0];
+            if x < self.width && y < self.height() && self.label_at(x, y) != 0 {
+                stats[self.label_at(x, y) as usize - 1].skeleton_length += edge.length;
+            }
+        }
+
+        stats
+    }
+}
+
+pub fn stats_to_csv(stats: &[ComponentStats]) -> String {
+    let mut csv = "label,area,min_x,min_y,max_x,max_y,centroid_x,centroid_y,perimeter,skeleton_length\n".to_string();
+    for component in stats {
+        csv.push_str(&format!(
+            "{},{},{},{},{},{},{:.2},{:.2},{},{:.2}\n",
+            component.label,
+            component.area,
+            component.min_x,
+            component.min_y,
+            component.max_x,
+            component.max_y,
+            component.centroid_x,
+            component.centroid_y,
+            component.perimeter,
+            component.skeleton_length));
+    }
+    csv
+}
+
+/// Counts the background components enclosed by the foreground
+pub fn count_holes(grid: &BinaryGrid, adjacency: AdjacencyMode) -> usize {
+    if grid.width() == 0 || grid.height() == 0 {
+        return 0;
+    }
+
+    let background = grid.inverted();
+    let components = Components::label(&background, complementary(adjacency));
+
+    let mut touches_border = vec![false; components.count() + 1];
+    let (width, height) = (grid.width(), grid.height());
+    for x in 0..width {
+        touches_border[components.label_at(x, 0) as usize] = true;
+        touches_border[components.label_at(x, height - 1) as usize] = true;
+    }
+    for y in 0..height {
+        touches_border[components.label_at(0, y) as usize] = true;
+        touches_border[components.label_at(width - 1, y) as usize] = true;
+    }
+
+    touches_border[1..].iter().filter(|&&touches| !touches).count()
+}
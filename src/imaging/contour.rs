@@ -0,0 +1,267 @@
+// contour.rs - Outer and hole boundaries of the shapes with their hierarchy
+// Copyright (C) 2019 Denis Karpovskiy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use serde::Serialize;
+use super::binary::{ BinaryGrid, NEIGHBOURS };
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ContourFormat {
+    Svg,
+    Json
+}
+
+impl ContourFormat {
+    /// Parses "svg" or "json", also used for file extensions
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "svg" => Some(ContourFormat::Svg),
+            "json" => Some(ContourFormat::Json),
+            _ => None
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContourKind {
+    /// The boundary between a component and the background around it
+    Outer,
+    /// The boundary between a component and a hole inside it
+    Hole
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Contour {
+    pub id: usize,
+    pub kind: ContourKind,
+    /// The hole an outer contour lies in or the outer contour around a hole,
+    /// none for the shapes on the background of the image
+    pub parent: Option<usize>,
+    /// The boundary pixels in the order they are followed
+    pub points: Vec<(u32, u32)>
+}
+
+#[derive(Debug, Serialize)]
+pub struct Contours {
+    pub width: u32,
+    pub height: u32,
+    pub contours: Vec<Contour>
+}
+
+fn direction(from: (i64, i64), to: (i64, i64)) -> usize {
+    let offset = (to.0 - from.0, to.1 - from.1);
+    NEIGHBOURS.iter().position(|&neighbour| neighbour == offset).unwrap()
+}
+
+// Border following of Suzuki and Abe on a copy of the grid with a background frame,
+// the foreground is 8-connected and the holes are 4-connected
+struct Follower {
+    width: i64,
+    /// 0 for the background, 1 for unvisited foreground and the signed border numbers
+    labels: Vec<i64>,
+    /// Kinds and parents by border number, 1 is the frame
+    kinds: Vec<ContourKind>,
+    parents: Vec<Option<usize>>,
+    contours: Vec<Contour>
+}
+
+impl Follower {
+    fn new(grid: &BinaryGrid) -> Self {
+        let width = grid.width() as i64 + 2;
+        let height = grid.height() as i64 + 2;
+        let labels = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| grid.get(x - 1, y - 1) as i64)
+            .collect();
+
+        Follower {
+            width,
+            labels,
+            kinds: vec![ContourKind::Hole, ContourKind::Hole],
+            parents: vec![None, None],
+            contours: Vec::new()
+        }
+    }
+
+    fn label(&self, (x, y): (i64, i64)) -> i64 {
+        self.labels[(y * self.width + x) as usize]
+    }
+
+    fn set_label(&mut self, (x, y): (i64, i64), label: i64) {
+        self.labels[(y * self.width + x) as usize] = label;
+    }
+
+    fn neighbour((x, y): (i64, i64), direction: usize) -> (i64, i64) {
+        let (dx, dy) = NEIGHBOURS[direction % 8];
+        (x + dx, y + dy)
+    }
+
+    fn follow(&mut self, start: (i64, i64), from: (i64, i64), border: i64) -> Vec<(u32, u32)> {
+        let to_point = |(x, y): (i64, i64)| ((x - 1) as u32, (y - 1) as u32);
+
+        // the first foreground neighbour clockwise, none for an isolated pixel
+        let first = direction(start, from);
+        let found = (first..first + 8)
+            .map(|direction| Self::neighbour(start, direction))
+            .find(|&pixel| self.label(pixel) != 0);
+        let first_neighbour = match found {
+            Some(pixel) => pixel,
+            None => {
+                self.set_label(start, -border);
+                return vec![to_point(start)];
+            }
+        };
+
+        let mut points = Vec::new();
+        let (mut previous, mut current) = (first_neighbour, start);
+        loop {
+            points.push(to_point(current));
+
+            // the next foreground neighbour counterclockwise after the previous pixel
+            let back = direction(current, previous);
+            let mut east_is_background = false;
+            let mut next = previous;
+            for step in 1..=8 {
+                let direction = (back + 8 - step) % 8;
+                let pixel = Self::neighbour(current, direction);
+                if self.label(pixel) != 0 {
+                    next = pixel;
+                    break;
+                }
+                if NEIGHBOURS[direction] == (1, 0) {
+                    east_is_background = true;
+                }
+            }
+
+            if east_is_background {
+                self.set_label(current, -border);
+            } else if self.label(current) == 1 {
+                self.set_label(current, border);
+            }
+
+            if next == start && current == first_neighbour {
+                return points;
+            }
+            previous = current;
+            current = next;
+        }
+    }
+
+    fn trace(mut self) -> Vec<Contour> {
+        let height = self.labels.len() as i64 / self.width;
+        let mut border = 1;
+
+        for y in 1..height - 1 {
+            let mut last_border = 1;
+            for x in 1..self.width - 1 {
+                let label = self.label((x, y));
+                if label == 0 {
+                    continue;
+                }
+
+                let start = if label == 1 && self.label((x - 1, y)) == 0 {
+                    Some((ContourKind::Outer, (x - 1, y)))
+                } else if label >= 1 && self.label((x + 1, y)) == 0 {
+                    if label > 1 {
+                        last_border = label;
+                    }
+                    Some((ContourKind::Hole, (x + 1, y)))
+                } else {
+                    None
+                };
+
+                if let Some((kind, from)) = start {
+                    border += 1;
+                    let enclosing = last_border as usize;
+                    let parent = if kind == self.kinds[enclosing] {
+                        self.parents[enclosing]
+                    } else {
+                        Some(enclosing)
+                    };
+                    self.kinds.push(kind);
+                    self.parents.push(parent);
+
+                    let points = self.follow((x, y), from, border);
+                    self.contours.push(Contour {
+                        id: border as usize - 2,
+                        kind,
+                        // the frame isn't a contour
+                        parent: parent.filter(|&parent| parent > 1).map(|parent| parent - 2),
+                        points
+                    });
+                }
+
+                let label = self.label((x, y));
+                if label != 1 {
+                    last_border = label.abs();
+                }
+            }
+        }
+
+        self.contours
+    }
+}
+
+/// Follows the boundaries of every component and hole
+pub fn trace_contours(grid: &BinaryGrid) -> Contours {
+    Contours {
+        width: grid.width(),
+        height: grid.height(),
+        contours: Follower::new(grid).trace()
+    }
+}
+
+impl Contours {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    /// Every contour becomes a closed path through the pixel centres, the holes
+    /// are cut out by the even-odd rule when the paths are filled
+    pub fn to_svg(&self) -> String {
+        let mut svg = format!(
+            concat!(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+                "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">\n",
+                "  <g fill=\"none\" stroke=\"black\" stroke-width=\"1\" fill-rule=\"evenodd\">\n"),
+            w = self.width, h = self.height);
+
+        for contour in &self.contours {
+            let points: Vec<String> = contour.points
+                .iter()
+                .map(|&(x, y)| format!("{} {}", x as f64 + 0.5, y as f64 + 0.5))
+                .collect();
+            svg.push_str(&format!(
+                "    <path id=\"contour{}\" class=\"{}\" d=\"M {} Z\"/>\n",
+                contour.id,
+                match contour.kind {
+                    ContourKind::Outer => "outer",
+                    ContourKind::Hole => "hole"
+                },
+                points.join(" L ")));
+        }
+
+        svg.push_str("  </g>\n</svg>\n");
+        svg
+    }
+
+    pub fn encode(&self, format: ContourFormat) -> String {
+        match format {
+            ContourFormat::Svg => self.to_svg(),
+            ContourFormat::Json => self.to_json()
+        }
+    }
+}
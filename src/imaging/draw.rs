@@ -0,0 +1,103 @@
+// draw.rs - Pixel editing of binary images
+// Copyright (C) 2019 Denis Karpovskiy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use improc_petrsu::AdjacencyMode;
+use super::binary::BinaryGrid;
+use super::components;
+
+/// The pixels of a line between the points (Bresenham), both ends included
+pub fn line_points(from: (i64, i64), to: (i64, i64)) -> Vec<(i64, i64)> {
+    let (dx, dy) = ((to.0 - from.0).abs(), -(to.1 - from.1).abs());
+    let (step_x, step_y) = ((to.0 - from.0).signum(), (to.1 - from.1).signum());
+    let (mut x, mut y) = from;
+    let mut error = dx + dy;
+    let mut points = Vec::with_capacity((dx - dy) as usize + 1);
+
+    loop {
+        points.push((x, y));
+        if (x, y) == to {
+            return points;
+        }
+
+        let doubled = 2 * error;
+        if doubled >= dy {
+            error += dy;
+            x += step_x;
+        }
+        if doubled <= dx {
+            error += dx;
+            y += step_y;
+        }
+    }
+}
+
+/// Offsets of a round brush `size` pixels across
+pub fn brush_offsets(size: u32) -> Vec<(i64, i64)> {
+    let size = size.max(1) as i64;
+    // even sizes lean to the top left of the centre pixel
+    let (low, high) = (-(size / 2), (size - 1) / 2);
+    let radius = size as f64 / 2.0;
+    let centre = (low + high) as f64 / 2.0;
+
+    let mut offsets = Vec::new();
+    for dy in low..=high {
+        for dx in low..=high {
+            let (fx, fy) = (dx as f64 - centre, dy as f64 - centre);
+            if fx * fx + fy * fy <= radius * radius {
+                offsets.push((dx, dy));
+            }
+        }
+    }
+    offsets
+}
+
+/// Stamps the brush at every pixel of the line, pixels outside the grid are skipped
+pub fn draw_line(grid: &mut BinaryGrid, from: (i64, i64), to: (i64, i64), size: u32, value: bool) {
+    let brush = brush_offsets(size);
+    for (x, y) in line_points(from, to) {
+        for &(dx, dy) in &brush {
+            let (px, py) = (x + dx, y + dy);
+            if px >= 0 && py >= 0 && px < grid.width() as i64 && py < grid.height() as i64 {
+                grid.set(px as u32, py as u32, value);
+            }
+        }
+    }
+}
+
+/// Sets the connected region of equal pixels around the seed to `value`, the
+/// foreground spreads with `adjacency` and the background with the complementary one
+pub fn flood_fill(grid: &mut BinaryGrid, seed: (u32, u32), value: bool, adjacency: AdjacencyMode) {
+    let original = grid.at(seed.0, seed.1);
+    if original == value {
+        return;
+    }
+
+    let adjacency = if original { adjacency } else { components::complementary(adjacency) };
+    let offsets = components::neighbour_offsets(adjacency);
+    let mut stack = vec![seed];
+    grid.set(seed.0, seed.1, value);
+
+    while let Some((x, y)) = stack.pop() {
+        for &(dx, dy) in offsets {
+            let (nx, ny) = (x as i64 + dx, y as i64 + dy);
+            let inside = nx >= 0 && ny >= 0 && nx < grid.width() as i64 && ny < grid.height() as i64;
+            if inside && grid.at(nx as u32, ny as u32) == original {
+                grid.set(nx as u32, ny as u32, value);
+                stack.push((nx as u32, ny as u32));
+            }
+        }
+    }
+}
@@ -0,0 +1,308 @@
+// filters.rs - Grayscale preprocessing applied before binarization
+// Copyright (C) 2019 Denis Karpovskiy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use image::{ imageops, DynamicImage, GrayImage, Luma };
+use std::collections::VecDeque;
+
+/// Weights of the red, green and blue channels in the gray value, they add up to 1
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ChannelWeights {
+    pub red: f64,
+    pub green: f64,
+    pub blue: f64
+}
+
+impl ChannelWeights {
+    /// The Rec. 709 luma, as used by the `image` crate
+    pub const LUMA: ChannelWeights = ChannelWeights { red: 0.2126, green: 0.7152, blue: 0.0722 };
+
+    /// Normalizes the weights so that they add up to 1
+    pub fn new(red: f64, green: f64, blue: f64) -> Result<Self, String> {
+        let sum = red + green + blue;
+        if red < 0.0 || green < 0.0 || blue < 0.0 || sum <= 0.0 {
+            return Err("The channel weights must not be negative and must not all be zero".to_string());
+        }
+        Ok(ChannelWeights { red: red / sum, green: green / sum, blue: blue / sum })
+    }
+
+    /// One of luma, average, red, green and blue
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "luma" => Some(ChannelWeights::LUMA),
+            "average" => Some(ChannelWeights { red: 1.0 / 3.0, green: 1.0 / 3.0, blue: 1.0 / 3.0 }),
+            "red" => Some(ChannelWeights { red: 1.0, green: 0.0, blue: 0.0 }),
+            "green" => Some(ChannelWeights { red: 0.0, green: 1.0, blue: 0.0 }),
+            "blue" => Some(ChannelWeights { red: 0.0, green: 0.0, blue: 1.0 }),
+            _ => None
+        }
+    }
+
+    pub fn to_gray(&self, image: &DynamicImage) -> GrayImage {
+        let rgb = image.to_rgb();
+        GrayImage::from_fn(rgb.width(), rgb.height(), |x, y| {
+            let pixel = rgb.get_pixel(x, y);
+            let value = pixel[0] as f64 * self.red + pixel[1] as f64 * self.green + pixel[2] as f64 * self.blue;
+            Luma([value.round().clamp(0.0, 255.0) as u8])
+        })
+    }
+}
+
+/// A filter that turns the image into a cleaner grayscale one
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PreprocessFilter {
+    Grayscale(ChannelWeights),
+    /// The standard deviation in pixels
+    GaussianBlur(f32),
+    /// The radius of the square window in pixels
+    MedianBlur(u32),
+    /// Stretches the gray levels to the full range, ignoring the darkest
+    /// and the lightest percent of the pixels
+    StretchContrast(f64),
+    /// Values above 1 lighten the midtones
+    Gamma(f64),
+    EqualizeHistogram,
+    /// Evens out the lighting with the background estimated over the radius,
+    /// which has to be wider than the strokes
+    SubtractBackground(u32)
+}
+
+impl PreprocessFilter {
+    /// Applies the filter, the result is a grayscale image
+    pub fn apply(&self, image: &DynamicImage) -> DynamicImage {
+        let gray = match self {
+            PreprocessFilter::Grayscale(weights) => weights.to_gray(image),
+            PreprocessFilter::GaussianBlur(sigma) => imageops::blur(&image.to_luma(), *sigma),
+            PreprocessFilter::MedianBlur(radius) => median_blur(&image.to_luma(), *radius),
+            PreprocessFilter::StretchContrast(clip) => stretch_contrast(&image.to_luma(), *clip),
+            PreprocessFilter::Gamma(gamma) => adjust_gamma(&image.to_luma(), *gamma),
+            PreprocessFilter::EqualizeHistogram => equalize_histogram(&image.to_luma()),
+            PreprocessFilter::SubtractBackground(radius) => subtract_background(&image.to_luma(), *radius)
+        };
+        DynamicImage::ImageLuma8(gray)
+    }
+
+    /// The entry of the processing history
+    pub fn description(&self) -> String {
+        match self {
+            PreprocessFilter::Grayscale(weights) => format!(
+                "Converted to grayscale with the channel weights {:.3}, {:.3}, {:.3}",
+                weights.red, weights.green, weights.blue),
+            PreprocessFilter::GaussianBlur(sigma) => format!("Applied a Gaussian blur with sigma {}", sigma),
+            PreprocessFilter::MedianBlur(radius) => format!("Applied a median blur of radius {}", radius),
+            PreprocessFilter::StretchContrast(clip) =>
+                format!("Stretched the contrast, clipping {}% of the pixels at each end", clip),
+            PreprocessFilter::Gamma(gamma) => format!("Adjusted the gamma by {}", gamma),
+            PreprocessFilter::EqualizeHistogram => "Equalized the histogram".to_string(),
+            PreprocessFilter::SubtractBackground(radius) =>
+                format!("Subtracted the background estimated over {} pixels", radius)
+        }
+    }
+}
+
+fn histogram(image: &GrayImage) -> [u64; 256] {
+    let mut histogram = [0u64; 256];
+    for pixel in image.pixels() {
+        histogram[pixel[0] as usize] += 1;
+    }
+    histogram
+}
+
+fn map_levels(image: &GrayImage, table: &[u8; 256]) -> GrayImage {
+    let mut result = image.clone();
+    for pixel in result.pixels_mut() {
+        pixel[0] = table[pixel[0] as usize];
+    }
+    result
+}
+
+/// The median of the square window, with the edge pixels repeated outside the image.
+/// The window slides along the rows keeping its histogram and the median up to date.
+pub fn median_blur(image: &GrayImage, radius: u32) -> GrayImage {
+    let (width, height) = image.dimensions();
+    if width == 0 || height == 0 || radius == 0 {
+        return image.clone();
+    }
+
+    let radius = radius as i64;
+    let at = |x: i64, y: i64| {
+        image.get_pixel(x.clamp(0, width as i64 - 1) as u32, y.clamp(0, height as i64 - 1) as u32)[0] as usize
+    };
+    let half = ((2 * radius + 1) * (2 * radius + 1) / 2) as u64;
+    let mut result = GrayImage::new(width, height);
+
+    for y in 0..height as i64 {
+        let mut histogram = [0u64; 256];
+        for dy in -radius..=radius {
+            for dx in -radius..=radius {
+                histogram[at(dx, y + dy)] += 1;
+            }
+        }
+
+        // the median and the number of window pixels below it
+        let (mut median, mut below) = (0usize, 0u64);
+        for x in 0..width as i64 {
+            if x > 0 {
+                for dy in -radius..=radius {
+                    let removed = at(x - radius - 1, y + dy);
+                    let added = at(x + radius, y + dy);
+                    histogram[removed] -= 1;
+                    histogram[added] += 1;
+                    if removed < median {
+                        below -= 1;
+                    }
+                    if added < median {
+                        below += 1;
+                    }
+                }
+            }
+
+            while below > half {
+                median -= 1;
+                below -= histogram[median];
+            }
+            while below + histogram[median] <= half {
+                below += histogram[median];
+                median += 1;
+            }
+            result.put_pixel(x as u32, y as u32, Luma([median as u8]));
+        }
+    }
+    result
+}
+
+// The first level with more than the clipped pixels at or beyond it
+fn clip_level<I: Iterator<Item = usize>>(histogram: &[u64; 256], levels: I, clipped: u64) -> Option<usize> {
+    let mut count = 0;
+    for level in levels {
+        count += histogram[level];
+        if count > clipped {
+            return Some(level);
+        }
+    }
+    None
+}
+
+pub fn stretch_contrast(image: &GrayImage, clip_percent: f64) -> GrayImage {
+    let histogram = histogram(image);
+    let total: u64 = histogram.iter().sum();
+    let clipped = (total as f64 * clip_percent.max(0.0) / 100.0) as u64;
+
+    let (low, high) = match (clip_level(&histogram, 0..256, clipped), clip_level(&histogram, (0..256).rev(), clipped)) {
+        (Some(low), Some(high)) if high > low => (low as f64, high as f64),
+        _ => return image.clone()
+    };
+
+    let mut table = [0u8; 256];
+    for (level, value) in table.iter_mut().enumerate() {
+        *value = ((level as f64 - low) * 255.0 / (high - low)).round().clamp(0.0, 255.0) as u8;
+    }
+    map_levels(image, &table)
+}
+
+pub fn adjust_gamma(image: &GrayImage, gamma: f64) -> GrayImage {
+    let mut table = [0u8; 256];
+    for (level, value) in table.iter_mut().enumerate() {
+        *value = (255.0 * (level as f64 / 255.0).powf(1.0 / gamma)).round().clamp(0.0, 255.0) as u8;
+    }
+    map_levels(image, &table)
+}
+
+pub fn equalize_histogram(image: &GrayImage) -> GrayImage {
+    let histogram = histogram(image);
+    let total: u64 = histogram.iter().sum();
+    let mut cumulative = [0u64; 256];
+    let mut sum = 0;
+    for (level, count) in histogram.iter().enumerate() {
+        sum += count;
+        cumulative[level] = sum;
+    }
+
+    let first = cumulative.iter().copied().find(|&count| count > 0).unwrap_or(0);
+    if total == first {
+        return image.clone();
+    }
+
+    let mut table = [0u8; 256];
+    for (value, &count) in table.iter_mut().zip(cumulative.iter()) {
+        *value = (count.saturating_sub(first) as f64 * 255.0 / (total - first) as f64).round() as u8;
+    }
+    map_levels(image, &table)
+}
+
+// The maximum of the window [i - radius, i + radius] of every value,
+// the candidates are kept in decreasing order
+fn max_filter_line(values: &[u8], radius: usize, result: &mut [u8]) {
+    let mut candidates: VecDeque<usize> = VecDeque::new();
+    let mut next = 0;
+    for (i, value) in result.iter_mut().enumerate() {
+        let end = (i + radius).min(values.len() - 1);
+        while next <= end {
+            while let Some(&back) = candidates.back() {
+                if values[back] > values[next] {
+                    break;
+                }
+                candidates.pop_back();
+            }
+            candidates.push_back(next);
+            next += 1;
+        }
+        while let Some(&front) = candidates.front() {
+            if front + radius >= i {
+                break;
+            }
+            candidates.pop_front();
+        }
+        *value = values[candidates[0]];
+    }
+}
+
+/// The lightest value around every pixel, the paper shows through the dark strokes
+fn estimate_background(image: &GrayImage, radius: u32) -> GrayImage {
+    let (width, height) = (image.width() as usize, image.height() as usize);
+    let radius = radius as usize;
+    let mut rows = vec![0u8; width * height];
+    for (source, target) in image.as_raw().chunks(width).zip(rows.chunks_mut(width)) {
+        max_filter_line(source, radius, target);
+    }
+
+    let mut background = GrayImage::new(width as u32, height as u32);
+    let mut column = vec![0u8; height];
+    let mut filtered = vec![0u8; height];
+    for x in 0..width {
+        for (y, value) in column.iter_mut().enumerate() {
+            *value = rows[y * width + x];
+        }
+        max_filter_line(&column, radius, &mut filtered);
+        for (y, &value) in filtered.iter().enumerate() {
+            background.put_pixel(x as u32, y as u32, Luma([value]));
+        }
+    }
+    background
+}
+
+/// Divides every pixel by the estimated background, so that the paper becomes white
+pub fn subtract_background(image: &GrayImage, radius: u32) -> GrayImage {
+    if image.width() == 0 || image.height() == 0 {
+        return image.clone();
+    }
+
+    let background = estimate_background(image, radius);
+    GrayImage::from_fn(image.width(), image.height(), |x, y| {
+        let value = image.get_pixel(x, y)[0] as u32;
+        let light = (background.get_pixel(x, y)[0] as u32).max(1);
+        Luma([(value * 255 / light).min(255) as u8])
+    })
+}
@@ -0,0 +1,235 @@
+// geometry.rs - Rotation, flipping, deskewing and resampling of whole pages
+// Copyright (C) 2019 Denis Karpovskiy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use image::{ imageops::FilterType, DynamicImage, GenericImageView, Rgb, RgbImage };
+use super::binary::BinaryGrid;
+
+// Steps of the search for the skew angle, in degrees
+const COARSE_STEP: f64 = 0.5;
+const FINE_STEP: f64 = 0.05;
+const RIGHT_ANGLE_TOLERANCE: f64 = 1e-9;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FlipDirection {
+    Horizontal,
+    Vertical
+}
+
+impl FlipDirection {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "horizontal" => Some(FlipDirection::Horizontal),
+            "vertical" => Some(FlipDirection::Vertical),
+            _ => None
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FlipDirection::Horizontal => "horizontal",
+            FlipDirection::Vertical => "vertical"
+        }
+    }
+}
+
+/// A transform that changes the geometry of the whole page
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GeometricTransform {
+    /// Clockwise, in degrees
+    Rotate(f64),
+    Flip(FlipDirection),
+    /// Straightens the text lines, searching up to the angle in degrees
+    Deskew(f64),
+    /// Scales the page to the resolution, from the stored one unless
+    /// the source resolution is given
+    Resample { dpi: f64, source_dpi: Option<f64> }
+}
+
+impl GeometricTransform {
+    /// Applies the transform, resampling reads and updates the resolution and
+    /// quarter turns swap its horizontal and vertical values
+    pub fn apply(&self, image: &DynamicImage, resolution: &mut Option<(f64, f64)>) -> Result<DynamicImage, String> {
+        match *self {
+            GeometricTransform::Rotate(degrees) => {
+                // a quarter turn makes the columns rows, the resolutions swap with them
+                let quarter_turn = [90.0, 270.0]
+                    .iter()
+                    .any(|angle| (degrees.rem_euclid(360.0) - angle).abs() < RIGHT_ANGLE_TOLERANCE);
+                if quarter_turn {
+                    *resolution = resolution.map(|(x, y)| (y, x));
+                }
+                Ok(rotate(image, degrees))
+            },
+            GeometricTransform::Flip(direction) => Ok(flip(image, direction)),
+            GeometricTransform::Deskew(max_angle) => Ok(deskew(image, max_angle).0),
+            GeometricTransform::Resample { dpi, source_dpi } => {
+                let (source_x, source_y) = source_dpi
+                    .map(|source| (source, source))
+                    .or(*resolution)
+                    .ok_or_else(|| "The image doesn't store its resolution, give the source DPI".to_string())?;
+                *resolution = Some((dpi, dpi));
+                Ok(resample(image, dpi / source_x, dpi / source_y))
+            }
+        }
+    }
+
+    /// The entry of the processing history
+    pub fn description(&self) -> String {
+        match self {
+            GeometricTransform::Rotate(degrees) => format!("Rotated by {} degrees clockwise", degrees),
+            GeometricTransform::Flip(direction) => format!("Flipped {}ly", direction.name()),
+            GeometricTransform::Deskew(max_angle) => format!("Deskewed by up to {} degrees", max_angle),
+            GeometricTransform::Resample { dpi, .. } => format!("Resampled to {} DPI", dpi)
+        }
+    }
+}
+
+pub fn flip(image: &DynamicImage, direction: FlipDirection) -> DynamicImage {
+    match direction {
+        FlipDirection::Horizontal => image.fliph(),
+        FlipDirection::Vertical => image.flipv()
+    }
+}
+
+/// Rotates the image clockwise. Right angles are exact, other angles are
+/// interpolated and the canvas grows to hold the whole image, filled with white.
+pub fn rotate(image: &DynamicImage, degrees: f64) -> DynamicImage {
+    let degrees = degrees.rem_euclid(360.0);
+    let is_near = |angle: f64| (degrees - angle).abs() < RIGHT_ANGLE_TOLERANCE;
+    if is_near(0.0) || is_near(360.0) {
+        return image.clone();
+    } else if is_near(90.0) {
+        return image.rotate90();
+    } else if is_near(180.0) {
+        return image.rotate180();
+    } else if is_near(270.0) {
+        return image.rotate270();
+    }
+
+    let (width, height) = image.dimensions();
+    let (sin, cos) = degrees.to_radians().sin_cos();
+    let rotated_width = (width as f64 * cos.abs() + height as f64 * sin.abs()).ceil() as u32;
+    let rotated_height = (width as f64 * sin.abs() + height as f64 * cos.abs()).ceil() as u32;
+    rotate_onto(image, degrees, rotated_width.max(1), rotated_height.max(1))
+}
+
+// Rotates clockwise about the centre onto a canvas of the given size
+fn rotate_onto(image: &DynamicImage, degrees: f64, width: u32, height: u32) -> DynamicImage {
+    let source = image.to_rgb();
+    let (sin, cos) = degrees.to_radians().sin_cos();
+    let (source_x, source_y) = (source.width() as f64 / 2.0, source.height() as f64 / 2.0);
+    let (centre_x, centre_y) = (width as f64 / 2.0, height as f64 / 2.0);
+
+    DynamicImage::ImageRgb8(RgbImage::from_fn(width, height, |x, y| {
+        // the inverse rotation maps the pixel centre back into the source
+        let (dx, dy) = (x as f64 + 0.5 - centre_x, y as f64 + 0.5 - centre_y);
+        let u = cos * dx + sin * dy + source_x - 0.5;
+        let v = -sin * dx + cos * dy + source_y - 0.5;
+        sample(&source, u, v)
+    }))
+}
+
+// Bilinear interpolation where everything outside the image is white
+fn sample(image: &RgbImage, x: f64, y: f64) -> Rgb<u8> {
+    let (left, top) = (x.floor(), y.floor());
+    let (fx, fy) = (x - left, y - top);
+    let pixel = |px: f64, py: f64| {
+        if px < 0.0 || py < 0.0 || px >= image.width() as f64 || py >= image.height() as f64 {
+            Rgb([255, 255, 255])
+        } else {
+            *image.get_pixel(px as u32, py as u32)
+        }
+    };
+    let corners = [
+        (pixel(left, top), (1.0 - fx) * (1.0 - fy)),
+        (pixel(left + 1.0, top), fx * (1.0 - fy)),
+        (pixel(left, top + 1.0), (1.0 - fx) * fy),
+        (pixel(left + 1.0, top + 1.0), fx * fy)
+    ];
+    let channel = |c: usize| {
+        let value: f64 = corners.iter().map(|(pixel, weight)| pixel[c] as f64 * weight).sum();
+        value.round().clamp(0.0, 255.0) as u8
+    };
+    Rgb([channel(0), channel(1), channel(2)])
+}
+
+/// Finds the clockwise rotation that straightens the text lines: the rows of
+/// the rotated foreground are the most uneven when the lines are horizontal.
+pub fn skew_angle(image: &DynamicImage, max_angle: f64) -> f64 {
+    let grid = BinaryGrid::from_image(image);
+    let (centre_x, centre_y) = (grid.width() as f64 / 2.0, grid.height() as f64 / 2.0);
+    let points: Vec<(f64, f64)> = grid
+        .foreground()
+        .map(|(x, y)| (x as f64 - centre_x, y as f64 - centre_y))
+        .collect();
+    let half_diagonal = centre_x.hypot(centre_y).ceil() as i64 + 1;
+    let mut rows = vec![0u64; 2 * half_diagonal as usize + 1];
+
+    // sum of the squared row counts of the projection profile
+    let mut score = |angle: f64| {
+        let (sin, cos) = angle.to_radians().sin_cos();
+        rows.iter_mut().for_each(|count| *count = 0);
+        for &(x, y) in &points {
+            let row = (sin * x + cos * y).round() as i64 + half_diagonal;
+            rows[row as usize] += 1;
+        }
+        rows.iter().map(|&count| count * count).sum::<u64>()
+    };
+
+    let max_angle = max_angle.abs();
+    let mut best = (0.0, score(0.0));
+    let mut search = |from: f64, to: f64, step: f64, best: &mut (f64, u64)| {
+        let steps = ((to - from) / step).round() as i64;
+        for i in 0..=steps {
+            let angle = from + i as f64 * step;
+            let value = score(angle);
+            // ties keep the smaller rotation
+            if value > best.1 || (value == best.1 && angle.abs() < best.0.abs()) {
+                *best = (angle, value);
+            }
+        }
+    };
+
+    let coarse_steps = (max_angle / COARSE_STEP).floor();
+    search(-coarse_steps * COARSE_STEP, coarse_steps * COARSE_STEP, COARSE_STEP, &mut best);
+    let centre = best.0;
+    search(
+        (centre - COARSE_STEP).max(-max_angle),
+        (centre + COARSE_STEP).min(max_angle),
+        FINE_STEP,
+        &mut best
+    );
+    best.0
+}
+
+/// Rotates the page by the skew angle, keeping its size.
+/// Returns the straightened page and the angle.
+pub fn deskew(image: &DynamicImage, max_angle: f64) -> (DynamicImage, f64) {
+    let angle = skew_angle(image, max_angle);
+    let (width, height) = image.dimensions();
+    (rotate_onto(image, angle, width, height), angle)
+}
+
+/// Scales the image by the factors, at least to a single pixel
+pub fn resample(image: &DynamicImage, scale_x: f64, scale_y: f64) -> DynamicImage {
+    let (width, height) = image.dimensions();
+    let new_width = ((width as f64 * scale_x).round() as u32).max(1);
+    let new_height = ((height as f64 * scale_y).round() as u32).max(1);
+    if (new_width, new_height) == (width, height) {
+        return image.clone();
+    }
+    image.resize_exact(new_width, new_height, FilterType::Triangle)
+}
@@ -0,0 +1,408 @@
+// graph.rs - Traces skeletons into graphs of nodes and edges
+// Copyright (C) 2019 Denis Karpovskiy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use serde::Serialize;
+use std::collections::{ HashMap, HashSet };
+use super::binary::{ BinaryGrid, NEIGHBOURS };
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeKind {
+    /// The end of a line, a pixel with a single run of neighbours
+    Endpoint,
+    /// Where three or more lines meet, touching junction pixels form one node
+    Junction,
+    /// A single pixel without neighbours
+    Isolated,
+    /// A point picked on a closed line without endpoints and junctions
+    Loop
+}
+
+impl NodeKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            NodeKind::Endpoint => "endpoint",
+            NodeKind::Junction => "junction",
+            NodeKind::Isolated => "isolated",
+            NodeKind::Loop => "loop"
+        }
+    }
+}
+
+/// The role of a single skeleton pixel
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PixelKind {
+    Isolated,
+    Endpoint,
+    Line,
+    Junction
+}
+
+impl PixelKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            PixelKind::Isolated => "isolated",
+            PixelKind::Endpoint => "endpoint",
+            PixelKind::Line => "line",
+            PixelKind::Junction => "junction"
+        }
+    }
+}
+
+/// Classifies a foreground pixel by its crossing number, so the corners of
+/// 4-connected staircases and diagonal strokes stay line pixels. The pixels
+/// inside thick shapes have no crossings and count as line pixels too.
+pub fn classify_pixel(grid: &BinaryGrid, x: u32, y: u32) -> PixelKind {
+    match grid.crossing_number(x, y) {
+        0 if grid.neighbour_count(x, y) == 0 => PixelKind::Isolated,
+        0 | 2 => PixelKind::Line,
+        1 => PixelKind::Endpoint,
+        _ => PixelKind::Junction
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Node {
+    pub id: usize,
+    pub kind: NodeKind,
+    /// The centre of the node pixels
+    pub x: f64,
+    pub y: f64,
+    pub pixels: Vec<(u32, u32)>
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Edge {
+    pub source: usize,
+    pub target: usize,
+    /// The length of the pixel chain, diagonal steps count as sqrt(2)
+    pub length: f64,
+    /// The pixel chain including the node pixels at both ends
+    pub points: Vec<(u32, u32)>
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SkeletonGraph {
+    pub width: u32,
+    pub height: u32,
+    pub nodes: Vec<Node>,
+    pub edges: Vec<Edge>
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GraphFormat {
+    Json,
+    GraphMl,
+    Dot
+}
+
+impl GraphFormat {
+    /// Parses "json", "graphml" or "dot", also used for file extensions
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "json" => Some(GraphFormat::Json),
+            "graphml" => Some(GraphFormat::GraphMl),
+            "dot" | "gv" => Some(GraphFormat::Dot),
+            _ => None
+        }
+    }
+}
+
+fn step_length(from: (u32, u32), to: (u32, u32)) -> f64 {
+    if from.0 != to.0 && from.1 != to.1 {
+        std::f64::consts::SQRT_2
+    } else {
+        1.0
+    }
+}
+
+// The foreground neighbours, the horizontal and vertical ones first so that
+// a chain walks through the corners of a staircase instead of cutting them
+fn neighbour_pixels(grid: &BinaryGrid, (x, y): (u32, u32)) -> impl Iterator<Item = (u32, u32)> + '_ {
+    NEIGHBOURS
+        .iter()
+        .step_by(2)
+        .chain(NEIGHBOURS.iter().skip(1).step_by(2))
+        .map(move |&(dx, dy)| (x as i64 + dx, y as i64 + dy))
+        .filter(move |&(nx, ny)| grid.get(nx, ny))
+        .map(|(nx, ny)| (nx as u32, ny as u32))
+}
+
+struct Tracer<'a> {
+    grid: &'a BinaryGrid,
+    node_of: HashMap<(u32, u32), usize>,
+    visited: HashSet<(u32, u32)>,
+    direct_edges: HashSet<(usize, usize)>,
+    nodes: Vec<Node>,
+    edges: Vec<Edge>
+}
+
+impl<'a> Tracer<'a> {
+    fn add_node(&mut self, kind: NodeKind, pixels: Vec<(u32, u32)>) -> usize {
+        let id = self.nodes.len();
+        let count = pixels.len() as f64;
+        let x = pixels.iter().map(|pixel| pixel.0 as f64).sum::<f64>() / count;
+        let y = pixels.iter().map(|pixel| pixel.1 as f64).sum::<f64>() / count;
+
+        for &pixel in &pixels {
+            self.node_of.insert(pixel, id);
+        }
+        self.nodes.push(Node { id, kind, x, y, pixels });
+        id
+    }
+
+    // Follows the chain of line pixels that starts at the node
+    // pixel `start` and continues with `next`
+    fn trace(&mut self, start: (u32, u32), next: (u32, u32)) {
+        let source = self.node_of[&start];
+
+        if let Some(&target) = self.node_of.get(&next) {
+            let key = (source.min(target), source.max(target));
+            if target != source && self.direct_edges.insert(key) {
+                self.edges.push(Edge { source, target, length: step_length(start, next), points: vec![start, next] });
+            }
+            return;
+        }
+
+        if self.visited.contains(&next) {
+            return;
+        }
+
+        let mut points = vec![start];
+        let mut length = 0.0;
+        let mut previous = start;
+        let mut current = next;
+
+        let target = loop {
+            length += step_length(previous, current);
+            points.push(current);
+
+            if let Some(&target) = self.node_of.get(&current) {
+                break target;
+            }
+            self.visited.insert(current);
+
+            // one run of neighbours is behind, the chain goes on with the
+            // first neighbour that isn't. Going back into the starting node
+            // only closes a loop when there's nowhere else to go.
+            let candidates: Vec<(u32, u32)> = neighbour_pixels(self.grid, current)
+                .filter(|&pixel| pixel != previous && !self.visited.contains(&pixel))
+                .collect();
+            let next = candidates
+                .iter()
+                .find(|pixel| self.node_of.get(*pixel) != Some(&source))
+                .or_else(|| candidates.first());
+            match next {
+                Some(&pixel) => {
+                    previous = current;
+                    current = pixel;
+                },
+                // a chain running into itself can't happen on a proper skeleton
+                None => return
+            }
+        };
+
+        // two pixels of the same junction joined through a single pixel
+        if target == source && points.len() <= 3 {
+            return;
+        }
+
+        self.edges.push(Edge { source, target, length, points });
+    }
+}
+
+/// Traces a one pixel thick skeleton into a graph. The pixels are classified
+/// with `classify_pixel`, endpoints and touching junction pixels become nodes
+/// and the chains of line pixels between them become edges.
+pub fn trace_graph(grid: &BinaryGrid) -> SkeletonGraph {
+    let mut tracer = Tracer {
+        grid,
+        node_of: HashMap::new(),
+        visited: HashSet::new(),
+        direct_edges: HashSet::new(),
+        nodes: Vec::new(),
+        edges: Vec::new()
+    };
+
+    for (x, y) in grid.foreground() {
+        match classify_pixel(grid, x, y) {
+            PixelKind::Isolated => { tracer.add_node(NodeKind::Isolated, vec![(x, y)]); },
+            PixelKind::Endpoint => { tracer.add_node(NodeKind::Endpoint, vec![(x, y)]); },
+            PixelKind::Line => {},
+            PixelKind::Junction if !tracer.node_of.contains_key(&(x, y)) => {
+                // collect the touching junction pixels into one node
+                let mut cluster = vec![(x, y)];
+                let mut members: HashSet<(u32, u32)> = cluster.iter().cloned().collect();
+                let mut index = 0;
+                while index < cluster.len() {
+                    let pixel = cluster[index];
+                    for neighbour in neighbour_pixels(grid, pixel) {
+                        if classify_pixel(grid, neighbour.0, neighbour.1) == PixelKind::Junction
+                            && members.insert(neighbour) {
+                            cluster.push(neighbour);
+                        }
+                    }
+                    index += 1;
+                }
+                tracer.add_node(NodeKind::Junction, cluster);
+            },
+            PixelKind::Junction => {}
+        }
+    }
+
+    let mut node_pixels: Vec<(u32, u32)> = tracer.node_of.keys().cloned().collect();
+    node_pixels.sort_by_key(|&(x, y)| (y, x));
+    for pixel in node_pixels {
+        for next in neighbour_pixels(grid, pixel).collect::<Vec<_>>() {
+            if tracer.node_of.get(&next) != tracer.node_of.get(&pixel) {
+                tracer.trace(pixel, next);
+            }
+        }
+    }
+
+    // whatever is left are closed lines, each gets a node to start from
+    for pixel in grid.foreground() {
+        if tracer.visited.contains(&pixel) || tracer.node_of.contains_key(&pixel) {
+            continue;
+        }
+
+        tracer.add_node(NodeKind::Loop, vec![pixel]);
+        if let Some(next) = neighbour_pixels(grid, pixel).next() {
+            tracer.trace(pixel, next);
+        }
+    }
+
+    SkeletonGraph {
+        width: grid.width(),
+        height: grid.height(),
+        nodes: tracer.nodes,
+        edges: tracer.edges
+    }
+}
+
+impl SkeletonGraph {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    pub fn to_graphml(&self) -> String {
+        let mut xml = String::from(concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+            "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+            "  <key id=\"kind\" for=\"node\" attr.name=\"kind\" attr.type=\"string\"/>\n",
+            "  <key id=\"x\" for=\"node\" attr.name=\"x\" attr.type=\"double\"/>\n",
+            "  <key id=\"y\" for=\"node\" attr.name=\"y\" attr.type=\"double\"/>\n",
+            "  <key id=\"length\" for=\"edge\" attr.name=\"length\" attr.type=\"double\"/>\n",
+            "  <graph id=\"skeleton\" edgedefault=\"undirected\">\n"));
+
+        for node in &self.nodes {
+            xml.push_str(&format!(
+                "    <node id=\"n{}\"><data key=\"kind\">{}</data><data key=\"x\">{}</data><data key=\"y\">{}</data></node>\n",
+                node.id, node.kind.name(), node.x, node.y));
+        }
+        for (index, edge) in self.edges.iter().enumerate() {
+            xml.push_str(&format!(
+                "    <edge id=\"e{}\" source=\"n{}\" target=\"n{}\"><data key=\"length\">{}</data></edge>\n",
+                index, edge.source, edge.target, edge.length));
+        }
+
+        xml.push_str("  </graph>\n</graphml>\n");
+        xml
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("graph skeleton {\n");
+        for node in &self.nodes {
+            // the image y axis points down, the one of Graphviz points up
+            dot.push_str(&format!(
+                "    n{} [kind=\"{}\", pos=\"{},{}!\"];\n",
+                node.id, node.kind.name(), node.x, self.height as f64 - node.y));
+        }
+        for edge in &self.edges {
+            dot.push_str(&format!("    n{} -- n{} [length={:.3}];\n", edge.source, edge.target, edge.length));
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    pub fn encode(&self, format: GraphFormat) -> String {
+        match format {
+            GraphFormat::Json => self.to_json(),
+            GraphFormat::GraphMl => self.to_graphml(),
+            GraphFormat::Dot => self.to_dot()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn solid_block_is_traced() {
+        // not a skeleton, but the graph overlay and prune may get one
+        let mut block = BinaryGrid::new(300, 300);
+        for y in 0..300 {
+            for x in 0..300 {
+                block.set(x, y, true);
+            }
+        }
+
+        let graph = trace_graph(&block);
+        let node_pixels: usize = graph.nodes.iter().map(|node| node.pixels.len()).sum();
+        assert!(node_pixels <= 300 * 300);
+    }
+
+    #[test]
+    fn staircase_is_one_edge() {
+        let mut staircase = BinaryGrid::new(3, 3);
+        for &(x, y) in &[(0, 0), (1, 0), (1, 1), (2, 1), (2, 2)] {
+            staircase.set(x, y, true);
+        }
+
+        assert_eq!(classify_pixel(&staircase, 1, 0), PixelKind::Line);
+        assert_eq!(classify_pixel(&staircase, 1, 1), PixelKind::Line);
+        assert_eq!(classify_pixel(&staircase, 2, 1), PixelKind::Line);
+
+        let graph = trace_graph(&staircase);
+        assert_eq!(graph.nodes.len(), 2);
+        assert!(graph.nodes.iter().all(|node| node.kind == NodeKind::Endpoint));
+        assert_eq!(graph.edges.len(), 1);
+        assert_eq!(graph.edges[0].points.len(), 5);
+    }
+
+    #[test]
+    fn touching_junction_pixels_are_one_node() {
+        // a horizontal line with a branch going up from one pixel and a
+        // branch going down from the next one
+        let mut grid = BinaryGrid::new(7, 7);
+        for offset in 0..7 {
+            grid.set(offset, 3, true);
+        }
+        for offset in 0..3 {
+            grid.set(3, offset, true);
+            grid.set(4, offset + 4, true);
+        }
+
+        let graph = trace_graph(&grid);
+        let junctions: Vec<&Node> = graph.nodes.iter().filter(|node| node.kind == NodeKind::Junction).collect();
+        assert_eq!(junctions.len(), 1);
+        assert_eq!(junctions[0].pixels.len(), 2);
+        assert_eq!(graph.nodes.len(), 5);
+        assert_eq!(graph.edges.len(), 4);
+    }
+}
@@ -0,0 +1,250 @@
+// loader.rs - Loads single and multi-page images
+// Copyright (C) 2019 Denis Karpovskiy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use image::{
+    DynamicImage, ImageBuffer, ImageError, ImageFormat, ImageResult, Rgb, RgbImage,
+    error::{ DecodingError, ImageFormatHint, ParameterError, ParameterErrorKind }
+};
+use std::io::{ Cursor, Read, Seek };
+use std::str::FromStr;
+use tiff::ColorType;
+use tiff::decoder::{ Decoder, DecodingResult };
+use super::metadata::{ self, ImageMetadata };
+
+/// A set of 1-based page numbers, e.g. "1-3,5,8-".
+#[derive(Clone, Debug)]
+pub enum PageRange {
+    All,
+    Pages(Vec<(usize, usize)>)
+}
+
+impl PageRange {
+    /// Checks whether the page with the 0-based index is selected
+    pub fn contains(&self, index: usize) -> bool {
+        match self {
+            PageRange::All => true,
+            PageRange::Pages(ranges) => ranges
+                .iter()
+                .any(|&(first, last)| first <= index + 1 && index + 1 <= last)
+        }
+    }
+}
+
+impl Default for PageRange {
+    fn default() -> Self {
+        PageRange::All
+    }
+}
+
+impl FromStr for PageRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse_page = |page: &str| match page.trim().parse::<usize>() {
+            Ok(0) | Err(_) => Err(format!("Invalid page number: '{}'", page)),
+            Ok(page) => Ok(page)
+        };
+
+        let mut ranges = Vec::new();
+        for part in s.split(',') {
+            let range = match part.find('-') {
+                Some(pos) => {
+                    let first = parse_page(&part[..pos])?;
+                    let last = match part[pos + 1..].trim() {
+                        "" => usize::MAX,
+                        last => parse_page(last)?
+                    };
+                    (first, last)
+                },
+                None => {
+                    let page = parse_page(part)?;
+                    (page, page)
+                }
+            };
+
+            if range.0 > range.1 {
+                return Err(format!("Invalid page range: '{}'", part));
+            }
+
+            ranges.push(range);
+        }
+
+        Ok(PageRange::Pages(ranges))
+    }
+}
+
+/// How the pages are prepared before they are handed to the processing
+#[derive(Clone, Copy, Debug)]
+pub struct LoadOptions {
+    /// Rotate and flip the pages according to the EXIF orientation
+    pub apply_orientation: bool,
+    /// Composite transparent pixels over this colour, or keep the alpha
+    /// channel as is if there is none
+    pub background: Option<Rgb<u8>>,
+}
+
+impl Default for LoadOptions {
+    fn default() -> Self {
+        LoadOptions {
+            apply_orientation: true,
+            background: Some(Rgb([255, 255, 255]))
+        }
+    }
+}
+
+/// Decoded pages together with the metadata of the file
+pub struct Document {
+    pub pages: Vec<DynamicImage>,
+    pub metadata: ImageMetadata,
+}
+
+/// Decodes the selected pages of the encoded image. Formats without pages
+/// are treated as single-page documents.
+pub fn load_document(bytes: &[u8], range: &PageRange, options: &LoadOptions) -> ImageResult<Document> {
+    let mut pages = if image::guess_format(bytes)? == ImageFormat::Tiff {
+        load_tiff_pages(Cursor::new(bytes), range)?
+    } else if range.contains(0) {
+        vec![image::load_from_memory(bytes)?]
+    } else {
+        Vec::new()
+    };
+
+    if pages.is_empty() {
+        return Err(ImageError::Parameter(ParameterError::from_kind(
+            ParameterErrorKind::Generic("No pages in the selected range".to_string()))));
+    }
+
+    let mut metadata = metadata::read_metadata(bytes);
+
+    if options.apply_orientation {
+        if let Some(orientation) = metadata.orientation() {
+            pages = pages.iter().map(|page| apply_orientation(page, orientation)).collect();
+            metadata.reset_orientation();
+        }
+    }
+
+    if let Some(background) = options.background {
+        pages = pages
+            .into_iter()
+            .map(|page| if page.color().has_alpha() {
+                DynamicImage::ImageRgb8(composite_over(&page, background))
+            } else {
+                page
+            })
+            .collect();
+    }
+
+    Ok(Document { pages, metadata })
+}
+
+fn apply_orientation(image: &DynamicImage, orientation: u16) -> DynamicImage {
+    match orientation {
+        2 => image.fliph(),
+        3 => image.rotate180(),
+        4 => image.flipv(),
+        5 => image.rotate90().fliph(),
+        6 => image.rotate90(),
+        7 => image.rotate270().fliph(),
+        8 => image.rotate270(),
+        _ => image.clone()
+    }
+}
+
+fn composite_over(image: &DynamicImage, background: Rgb<u8>) -> RgbImage {
+    let rgba = image.to_rgba();
+    RgbImage::from_fn(rgba.width(), rgba.height(), |x, y| {
+        let pixel = rgba.get_pixel(x, y);
+        let alpha = pixel[3] as u32;
+        let blend = |channel: usize| {
+            ((pixel[channel] as u32 * alpha + background[channel] as u32 * (255 - alpha) + 127) / 255) as u8
+        };
+        Rgb([blend(0), blend(1), blend(2)])
+    })
+}
+
+fn tiff_error(err: tiff::TiffError) -> ImageError {
+    ImageError::Decoding(DecodingError::new(ImageFormatHint::Exact(ImageFormat::Tiff), err))
+}
+
+fn load_tiff_pages<R: Read + Seek>(reader: R, range: &PageRange) -> ImageResult<Vec<DynamicImage>> {
+    let mut decoder = Decoder::new(reader).map_err(tiff_error)?;
+    let mut pages = Vec::new();
+    let mut index = 0;
+
+    loop {
+        if range.contains(index) {
+            pages.push(decode_tiff_page(&mut decoder)?);
+        }
+
+        if !decoder.more_images() {
+            break;
+        }
+
+        decoder.next_image().map_err(tiff_error)?;
+        index += 1;
+    }
+
+    Ok(pages)
+}
+
+fn decode_tiff_page<R: Read + Seek>(decoder: &mut Decoder<R>) -> ImageResult<DynamicImage> {
+    let (width, height) = decoder.dimensions().map_err(tiff_error)?;
+    let color_type = decoder.colortype().map_err(tiff_error)?;
+    let data = decoder.read_image().map_err(tiff_error)?;
+
+    let image = match (color_type, data) {
+        (ColorType::Gray(8), DecodingResult::U8(buf)) =>
+            ImageBuffer::from_raw(width, height, buf).map(DynamicImage::ImageLuma8),
+        (ColorType::Gray(bits), DecodingResult::U8(buf)) if bits < 8 =>
+            ImageBuffer::from_raw(width, height, unpack_gray(&buf, width, bits))
+                .map(DynamicImage::ImageLuma8),
+        (ColorType::GrayA(8), DecodingResult::U8(buf)) =>
+            ImageBuffer::from_raw(width, height, buf).map(DynamicImage::ImageLumaA8),
+        (ColorType::RGB(8), DecodingResult::U8(buf)) =>
+            ImageBuffer::from_raw(width, height, buf).map(DynamicImage::ImageRgb8),
+        (ColorType::RGBA(8), DecodingResult::U8(buf)) =>
+            ImageBuffer::from_raw(width, height, buf).map(DynamicImage::ImageRgba8),
+        (ColorType::Gray(16), DecodingResult::U16(buf)) =>
+            ImageBuffer::from_raw(width, height, buf).map(DynamicImage::ImageLuma16),
+        (ColorType::RGB(16), DecodingResult::U16(buf)) =>
+            ImageBuffer::from_raw(width, height, buf).map(DynamicImage::ImageRgb16),
+        (ColorType::RGBA(16), DecodingResult::U16(buf)) =>
+            ImageBuffer::from_raw(width, height, buf).map(DynamicImage::ImageRgba16),
+        _ => None
+    };
+
+    image.ok_or_else(|| ImageError::Decoding(DecodingError::new(
+        ImageFormatHint::Exact(ImageFormat::Tiff),
+        format!("Unsupported TIFF page layout: {:?}", color_type))))
+}
+
+// Samples with less than 8 bits are packed into bytes with every row
+// starting at a byte boundary
+fn unpack_gray(packed: &[u8], width: u32, bits: u8) -> Vec<u8> {
+    let width = width as usize;
+    let bits = bits as usize;
+    let row_bytes = (width * bits + 7) / 8;
+    let max = (1u16 << bits) - 1;
+
+    packed
+        .chunks(row_bytes)
+        .flat_map(|row| (0..width).map(move |x| {
+            let bit = x * bits;
+            let value = (row[bit / 8] >> (8 - bits - bit % 8)) as u16 & max;
+            (value * 255 / max) as u8
+        }))
+        .collect()
+}
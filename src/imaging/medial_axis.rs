@@ -0,0 +1,174 @@
+// medial_axis.rs - Euclidean distance transform and the skeleton annotated with the local radius
+// Copyright (C) 2019 Denis Karpovskiy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use image::{ ImageBuffer, Luma };
+use super::binary::BinaryGrid;
+
+/// Squared Euclidean distance of every pixel to the nearest background pixel,
+/// the area outside the image counts as background
+pub struct DistanceMap {
+    width: u32,
+    height: u32,
+    squared: Vec<u64>
+}
+
+// Lower envelope of the parabolas (Felzenszwalb and Huttenlocher), `f` holds the
+// squared distances along the line and is replaced by the transformed ones
+fn transform_line(f: &mut [u64]) {
+    let n = f.len();
+    // the virtual background pixels at -1 and n bound every distance
+    let border = |q: usize| {
+        let q = q as u64;
+        (q + 1).pow(2).min((n as u64 - q).pow(2))
+    };
+
+    let mut vertices = vec![0usize; n];
+    let mut boundaries = vec![0f64; n + 1];
+    let mut k = 0;
+    boundaries[0] = f64::NEG_INFINITY;
+    boundaries[1] = f64::INFINITY;
+
+    let intersection = |f: &[u64], q: usize, v: usize| {
+        ((f[q] + (q * q) as u64) as f64 - (f[v] + (v * v) as u64) as f64) / (2.0 * (q as f64 - v as f64))
+    };
+
+    for q in 1..n {
+        let mut s = intersection(f, q, vertices[k]);
+        while s <= boundaries[k] {
+            k -= 1;
+            s = intersection(f, q, vertices[k]);
+        }
+        k += 1;
+        vertices[k] = q;
+        boundaries[k] = s;
+        boundaries[k + 1] = f64::INFINITY;
+    }
+
+    let source = f.to_vec();
+    k = 0;
+    for (q, value) in f.iter_mut().enumerate() {
+        while boundaries[k + 1] < q as f64 {
+            k += 1;
+        }
+        let v = vertices[k];
+        let offset = (q as i64 - v as i64).pow(2) as u64;
+        *value = (offset + source[v]).min(border(q));
+    }
+}
+
+/// Computes the exact Euclidean distance transform of the foreground
+pub fn distance_transform(grid: &BinaryGrid) -> DistanceMap {
+    let (width, height) = (grid.width(), grid.height());
+    // larger than any distance inside the image, stays exact in f64
+    let infinity = (width as u64 + height as u64 + 2).pow(2);
+
+    let mut squared: Vec<u64> = (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .map(|(x, y)| if grid.at(x, y) { infinity } else { 0 })
+        .collect();
+
+    if width == 0 || height == 0 {
+        return DistanceMap { width, height, squared };
+    }
+
+    let width = width as usize;
+    let mut column = vec![0; height as usize];
+    for x in 0..width {
+        for (y, value) in column.iter_mut().enumerate() {
+            *value = squared[y * width + x];
+        }
+        transform_line(&mut column);
+        for (y, &value) in column.iter().enumerate() {
+            squared[y * width + x] = value;
+        }
+    }
+
+    for row in squared.chunks_mut(width) {
+        transform_line(row);
+    }
+
+    DistanceMap { width: grid.width(), height, squared }
+}
+
+impl DistanceMap {
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Distance in pixels, 0 for the background
+    pub fn at(&self, x: u32, y: u32) -> f64 {
+        (self.squared[(y * self.width + x) as usize] as f64).sqrt()
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct MedialPoint {
+    pub x: u32,
+    pub y: u32,
+    /// Distance to the nearest background pixel, half of the local stroke width
+    pub radius: f64
+}
+
+/// Skeleton pixels with the radius of the inscribed circle of the shape
+pub struct MedialAxis {
+    pub width: u32,
+    pub height: u32,
+    pub points: Vec<MedialPoint>
+}
+
+/// Annotates every skeleton pixel with the distance transform of the original shape
+pub fn medial_axis(shape: &BinaryGrid, skeleton: &BinaryGrid) -> MedialAxis {
+    let distances = distance_transform(shape);
+    let points = skeleton
+        .foreground()
+        .filter(|&(x, y)| x < distances.width() && y < distances.height())
+        .map(|(x, y)| MedialPoint { x, y, radius: distances.at(x, y) })
+        .collect();
+
+    MedialAxis {
+        width: skeleton.width(),
+        height: skeleton.height(),
+        points
+    }
+}
+
+impl MedialAxis {
+    pub fn max_radius(&self) -> f64 {
+        self.points.iter().map(|point| point.radius).fold(0.0, f64::max)
+    }
+
+    /// The radius multiplied by `scale` as 16-bit samples, 0 off the skeleton
+    pub fn to_gray16(&self, scale: f64) -> ImageBuffer<Luma<u16>, Vec<u16>> {
+        let mut image = ImageBuffer::new(self.width, self.height);
+        for point in &self.points {
+            let value = (point.radius * scale).round().clamp(0.0, u16::MAX as f64) as u16;
+            image.put_pixel(point.x, point.y, Luma([value]));
+        }
+        image
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("x,y,radius\n");
+        for point in &self.points {
+            csv.push_str(&format!("{},{},{:.3}\n", point.x, point.y, point.radius));
+        }
+        csv
+    }
+}
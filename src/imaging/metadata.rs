@@ -0,0 +1,424 @@
+// metadata.rs - Reads and writes resolution, ICC profile and EXIF metadata
+// Copyright (C) 2019 Denis Karpovskiy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use image::ImageFormat;
+use std::io::Cursor;
+use tiff::decoder::{ Decoder, ifd::Value };
+use tiff::tags::Tag;
+
+const PNG_SIGNATURE: [u8; 8] = [137, 80, 78, 71, 13, 10, 26, 10];
+const PNG_HISTORY_KEYWORD: &[u8] = b"Description";
+const JPEG_EXIF_HEADER: &[u8] = b"Exif\0\0";
+const JPEG_ICC_HEADER: &[u8] = b"ICC_PROFILE\0";
+const JPEG_MAX_SEGMENT: usize = 65533;
+// ICC chunks are numbered with a single byte
+const JPEG_MAX_ICC_CHUNKS: usize = 255;
+const METERS_PER_INCH: f64 = 0.0254;
+const EXIF_ORIENTATION_TAG: u16 = 0x0112;
+
+/// Metadata that survives processing. DPI is kept for every format, while the
+/// ICC profile and EXIF are only carried between PNG and JPEG files.
+#[derive(Clone, Debug, Default)]
+pub struct ImageMetadata {
+    /// Horizontal and vertical resolution in dots per inch
+    pub dpi: Option<(f64, f64)>,
+    pub icc_profile: Option<Vec<u8>>,
+    /// EXIF data in its TIFF layout, without the JPEG "Exif" header
+    pub exif: Option<Vec<u8>>,
+    /// Processing steps, stored in a text chunk (PNG), a comment (JPEG)
+    /// or the image description (TIFF)
+    pub history: Vec<String>,
+}
+
+impl ImageMetadata {
+    pub fn history_text(&self) -> String {
+        self.history.join("\n")
+    }
+
+    /// The EXIF orientation, from 1 (upright) to 8
+    pub fn orientation(&self) -> Option<u16> {
+        let exif = self.exif.as_ref()?;
+        let (pos, big_endian) = exif_orientation_offset(exif)?;
+        let bytes = [exif[pos], exif[pos + 1]];
+        Some(if big_endian { u16::from_be_bytes(bytes) } else { u16::from_le_bytes(bytes) })
+    }
+
+    /// Marks the image as upright once the orientation has been applied,
+    /// so that viewers don't rotate the saved image again
+    pub fn reset_orientation(&mut self) {
+        if let Some(exif) = self.exif.as_mut() {
+            if let Some((pos, big_endian)) = exif_orientation_offset(exif) {
+                let bytes = if big_endian { 1u16.to_be_bytes() } else { 1u16.to_le_bytes() };
+                exif[pos..pos + 2].copy_from_slice(&bytes);
+            }
+        }
+    }
+}
+
+// Finds the value of the orientation tag in the first IFD of the EXIF data
+fn exif_orientation_offset(exif: &[u8]) -> Option<(usize, bool)> {
+    let big_endian = match exif.get(..2)? {
+        b"MM" => true,
+        b"II" => false,
+        _ => return None
+    };
+    let read_u16 = |pos: usize| -> Option<u16> {
+        let bytes = [*exif.get(pos)?, *exif.get(pos + 1)?];
+        Some(if big_endian { u16::from_be_bytes(bytes) } else { u16::from_le_bytes(bytes) })
+    };
+    let read_u32 = |pos: usize| -> Option<u32> {
+        let (first, second) = (read_u16(pos)? as u32, read_u16(pos + 2)? as u32);
+        Some(if big_endian { first << 16 | second } else { second << 16 | first })
+    };
+
+    let ifd = read_u32(4)? as usize;
+    let count = read_u16(ifd)? as usize;
+    (0..count)
+        .map(|index| ifd + 2 + index * 12)
+        .find(|&entry| read_u16(entry) == Some(EXIF_ORIENTATION_TAG))
+        .map(|entry| entry + 8)
+        .filter(|&pos| pos + 2 <= exif.len())
+        .map(|pos| (pos, big_endian))
+}
+
+/// Extracts whatever metadata the encoded image has. Unknown formats
+/// and malformed metadata result in empty fields.
+pub fn read_metadata(bytes: &[u8]) -> ImageMetadata {
+    match image::guess_format(bytes) {
+        Ok(ImageFormat::Png) => read_png_metadata(bytes),
+        Ok(ImageFormat::Jpeg) => read_jpeg_metadata(bytes),
+        Ok(ImageFormat::Tiff) => read_tiff_metadata(bytes),
+        _ => ImageMetadata::default()
+    }
+}
+
+/// Embeds the metadata into an already encoded PNG or JPEG image.
+/// Other formats are returned unchanged.
+pub fn embed_metadata(bytes: Vec<u8>, format: ImageFormat, metadata: &ImageMetadata) -> Vec<u8> {
+    match format {
+        ImageFormat::Png => embed_png_metadata(&bytes, metadata).unwrap_or(bytes),
+        ImageFormat::Jpeg => embed_jpeg_metadata(&bytes, metadata).unwrap_or(bytes),
+        _ => bytes
+    }
+}
+
+fn png_chunks(bytes: &[u8]) -> Vec<(&[u8], &[u8])> {
+    let mut chunks = Vec::new();
+    let mut pos = PNG_SIGNATURE.len();
+
+    while pos + 12 <= bytes.len() {
+        let length = u32::from_be_bytes([bytes[pos], bytes[pos + 1], bytes[pos + 2], bytes[pos + 3]]) as usize;
+        if pos + 12 + length > bytes.len() {
+            break;
+        }
+
+        chunks.push((&bytes[pos + 4..pos + 8], &bytes[pos + 8..pos + 8 + length]));
+        pos += 12 + length;
+    }
+
+    chunks
+}
+
+fn write_png_chunk(out: &mut Vec<u8>, name: &[u8], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = out.len();
+    out.extend_from_slice(name);
+    out.extend_from_slice(data);
+    let crc = crc32fast::hash(&out[start..]);
+    out.extend_from_slice(&crc.to_be_bytes());
+}
+
+// Whether the tEXt or iTXt chunk holds the processing history
+fn is_png_history(data: &[u8]) -> bool {
+    data.starts_with(PNG_HISTORY_KEYWORD) && data.get(PNG_HISTORY_KEYWORD.len()) == Some(&0)
+}
+
+// The UTF-8 text of an iTXt chunk: the keyword, the compression flag and
+// method, the language tag and the translated keyword come before it
+fn png_international_text(data: &[u8]) -> Option<Vec<u8>> {
+    let keyword_end = data.iter().position(|&b| b == 0)?;
+    let compressed = *data.get(keyword_end + 1)? == 1;
+    let rest = data.get(keyword_end + 3..)?;
+    let language_end = rest.iter().position(|&b| b == 0)?;
+    let rest = &rest[language_end + 1..];
+    let text = &rest[rest.iter().position(|&b| b == 0)? + 1..];
+
+    if compressed {
+        miniz_oxide::inflate::decompress_to_vec_zlib(text).ok()
+    } else {
+        Some(text.to_vec())
+    }
+}
+
+fn read_png_metadata(bytes: &[u8]) -> ImageMetadata {
+    let mut metadata = ImageMetadata::default();
+
+    for (name, data) in png_chunks(bytes) {
+        match name {
+            b"pHYs" if data.len() == 9 && data[8] == 1 => {
+                let x = u32::from_be_bytes([data[0], data[1], data[2], data[3]]);
+                let y = u32::from_be_bytes([data[4], data[5], data[6], data[7]]);
+                metadata.dpi = Some((x as f64 * METERS_PER_INCH, y as f64 * METERS_PER_INCH));
+            },
+            b"iCCP" => {
+                // profile name, null separator, compression method, zlib stream
+                if let Some(pos) = data.iter().position(|&b| b == 0) {
+                    if data.len() > pos + 2 {
+                        metadata.icc_profile = miniz_oxide::inflate::decompress_to_vec_zlib(&data[pos + 2..]).ok();
+                    }
+                }
+            },
+            b"eXIf" => metadata.exif = Some(data.to_vec()),
+            // tEXt holds Latin-1, older files may store the history there
+            b"tEXt" if is_png_history(data) => {
+                let text: String = data[PNG_HISTORY_KEYWORD.len() + 1..].iter().map(|&b| b as char).collect();
+                metadata.history = text.lines().map(String::from).collect();
+            },
+            b"iTXt" if is_png_history(data) => {
+                if let Some(text) = png_international_text(data) {
+                    metadata.history = String::from_utf8_lossy(&text).lines().map(String::from).collect();
+                }
+            },
+            _ => ()
+        }
+    }
+
+    metadata
+}
+
+fn embed_png_metadata(bytes: &[u8], metadata: &ImageMetadata) -> Option<Vec<u8>> {
+    if !bytes.starts_with(&PNG_SIGNATURE) {
+        return None;
+    }
+
+    let chunks = png_chunks(bytes);
+    let (ihdr_name, ihdr_data) = *chunks.first()?;
+
+    let mut out = PNG_SIGNATURE.to_vec();
+    write_png_chunk(&mut out, ihdr_name, ihdr_data);
+
+    if let Some((x, y)) = metadata.dpi {
+        let mut data = Vec::with_capacity(9);
+        data.extend_from_slice(&((x / METERS_PER_INCH).round() as u32).to_be_bytes());
+        data.extend_from_slice(&((y / METERS_PER_INCH).round() as u32).to_be_bytes());
+        data.push(1);
+        write_png_chunk(&mut out, b"pHYs", &data);
+    }
+
+    if let Some(profile) = &metadata.icc_profile {
+        let mut data = b"ICC profile\0\0".to_vec();
+        data.extend(miniz_oxide::deflate::compress_to_vec_zlib(profile, 6));
+        write_png_chunk(&mut out, b"iCCP", &data);
+    }
+
+    if let Some(exif) = &metadata.exif {
+        write_png_chunk(&mut out, b"eXIf", exif);
+    }
+
+    if !metadata.history.is_empty() {
+        // iTXt, as tEXt can't hold UTF-8: uncompressed, without
+        // a language tag and a translated keyword
+        let mut data = PNG_HISTORY_KEYWORD.to_vec();
+        data.extend_from_slice(&[0, 0, 0, 0, 0]);
+        data.extend(metadata.history_text().bytes());
+        write_png_chunk(&mut out, b"iTXt", &data);
+    }
+
+    for &(name, data) in &chunks[1..] {
+        let replaced = match name {
+            b"pHYs" | b"iCCP" | b"sRGB" | b"eXIf" => true,
+            b"tEXt" | b"iTXt" => is_png_history(data),
+            _ => false
+        };
+
+        if !replaced {
+            write_png_chunk(&mut out, name, data);
+        }
+    }
+
+    Some(out)
+}
+
+// Returns the marker and the payload of every segment before the scan data,
+// and the offset where the scan data begins
+fn jpeg_segments(bytes: &[u8]) -> Option<(Vec<(u8, &[u8])>, usize)> {
+    if !bytes.starts_with(&[0xFF, 0xD8]) {
+        return None;
+    }
+
+    let mut segments = Vec::new();
+    let mut pos = 2;
+
+    loop {
+        if pos + 4 > bytes.len() || bytes[pos] != 0xFF {
+            return None;
+        }
+
+        let marker = bytes[pos + 1];
+        if marker == 0xDA {
+            return Some((segments, pos));
+        }
+
+        let length = u16::from_be_bytes([bytes[pos + 2], bytes[pos + 3]]) as usize;
+        if length < 2 || pos + 2 + length > bytes.len() {
+            return None;
+        }
+
+        segments.push((marker, &bytes[pos + 4..pos + 2 + length]));
+        pos += 2 + length;
+    }
+}
+
+fn write_jpeg_segment(out: &mut Vec<u8>, marker: u8, data: &[u8]) {
+    out.extend_from_slice(&[0xFF, marker]);
+    out.extend_from_slice(&((data.len() + 2) as u16).to_be_bytes());
+    out.extend_from_slice(data);
+}
+
+fn read_jpeg_metadata(bytes: &[u8]) -> ImageMetadata {
+    let mut metadata = ImageMetadata::default();
+    let segments = match jpeg_segments(bytes) {
+        Some((segments, _)) => segments,
+        None => return metadata
+    };
+
+    let mut icc_chunks = Vec::new();
+    for (marker, data) in segments {
+        match marker {
+            0xE0 if data.len() >= 12 && data.starts_with(b"JFIF\0") => {
+                let x = u16::from_be_bytes([data[8], data[9]]) as f64;
+                let y = u16::from_be_bytes([data[10], data[11]]) as f64;
+                metadata.dpi = match data[7] {
+                    1 => Some((x, y)),
+                    2 => Some((x * 2.54, y * 2.54)),
+                    _ => None
+                };
+            },
+            0xE1 if data.starts_with(JPEG_EXIF_HEADER) => {
+                metadata.exif = Some(data[JPEG_EXIF_HEADER.len()..].to_vec());
+            },
+            0xE2 if data.len() > 14 && data.starts_with(JPEG_ICC_HEADER) => {
+                icc_chunks.push((data[12], &data[14..]));
+            },
+            0xFE => {
+                metadata.history = String::from_utf8_lossy(data)
+                    .lines()
+                    .map(String::from)
+                    .collect();
+            },
+            _ => ()
+        }
+    }
+
+    if !icc_chunks.is_empty() {
+        icc_chunks.sort_by_key(|&(sequence, _)| sequence);
+        metadata.icc_profile = Some(icc_chunks.iter().flat_map(|&(_, data)| data.iter().cloned()).collect());
+    }
+
+    metadata
+}
+
+fn embed_jpeg_metadata(bytes: &[u8], metadata: &ImageMetadata) -> Option<Vec<u8>> {
+    let (segments, scan_start) = jpeg_segments(bytes)?;
+    let mut out = vec![0xFF, 0xD8];
+    let is_jfif = |marker: u8, data: &[u8]| marker == 0xE0 && data.starts_with(b"JFIF\0");
+
+    // the JFIF segment comes first, the new one or the one of the input
+    if let Some((x, y)) = metadata.dpi {
+        let mut data = b"JFIF\0\x01\x02\x01".to_vec();
+        data.extend_from_slice(&(x.round() as u16).to_be_bytes());
+        data.extend_from_slice(&(y.round() as u16).to_be_bytes());
+        data.extend_from_slice(&[0, 0]);
+        write_jpeg_segment(&mut out, 0xE0, &data);
+    } else if let Some(&(marker, data)) = segments.iter().find(|&&(marker, data)| is_jfif(marker, data)) {
+        write_jpeg_segment(&mut out, marker, data);
+    }
+
+    if let Some(exif) = &metadata.exif {
+        if exif.len() + JPEG_EXIF_HEADER.len() <= JPEG_MAX_SEGMENT {
+            let mut data = JPEG_EXIF_HEADER.to_vec();
+            data.extend_from_slice(exif);
+            write_jpeg_segment(&mut out, 0xE1, &data);
+        }
+    }
+
+    if let Some(profile) = &metadata.icc_profile {
+        let chunks = profile.chunks(JPEG_MAX_SEGMENT - JPEG_ICC_HEADER.len() - 2).collect::<Vec<_>>();
+        // a profile too large to number its chunks is dropped, like an oversized EXIF
+        let chunks = if chunks.len() <= JPEG_MAX_ICC_CHUNKS { chunks } else { Vec::new() };
+        for (index, chunk) in chunks.iter().enumerate() {
+            let mut data = JPEG_ICC_HEADER.to_vec();
+            data.extend_from_slice(&[index as u8 + 1, chunks.len() as u8]);
+            data.extend_from_slice(chunk);
+            write_jpeg_segment(&mut out, 0xE2, &data);
+        }
+    }
+
+    if !metadata.history.is_empty() {
+        let text = metadata.history_text();
+        // cut a long history between characters, not inside one
+        let mut end = text.len().min(JPEG_MAX_SEGMENT);
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        write_jpeg_segment(&mut out, 0xFE, text[..end].as_bytes());
+    }
+
+    for (marker, data) in segments {
+        let replaced = match marker {
+            0xE0 => is_jfif(marker, data),
+            0xE1 => data.starts_with(JPEG_EXIF_HEADER),
+            0xE2 => data.starts_with(JPEG_ICC_HEADER),
+            0xFE => !metadata.history.is_empty(),
+            _ => false
+        };
+
+        if !replaced {
+            write_jpeg_segment(&mut out, marker, data);
+        }
+    }
+
+    out.extend_from_slice(&bytes[scan_start..]);
+    Some(out)
+}
+
+fn read_tiff_metadata(bytes: &[u8]) -> ImageMetadata {
+    let mut metadata = ImageMetadata::default();
+    let mut decoder = match Decoder::new(Cursor::new(bytes)) {
+        Ok(decoder) => decoder,
+        Err(_) => return metadata
+    };
+
+    let scale = match decoder.find_tag_unsigned::<u16>(Tag::ResolutionUnit) {
+        Ok(Some(3)) => Some(2.54),
+        Ok(Some(2)) | Ok(None) => Some(1.0),
+        _ => None
+    };
+    let x = decoder.find_tag(Tag::XResolution);
+    let y = decoder.find_tag(Tag::YResolution);
+
+    if let (Some(scale), Ok(Some(Value::Rational(xn, xd))), Ok(Some(Value::Rational(yn, yd)))) = (scale, x, y) {
+        if xd != 0 && yd != 0 {
+            metadata.dpi = Some((xn as f64 / xd as f64 * scale, yn as f64 / yd as f64 * scale));
+        }
+    }
+
+    if let Ok(Some(Value::Ascii(description))) = decoder.find_tag(Tag::ImageDescription) {
+        metadata.history = description.lines().map(String::from).collect();
+    }
+
+    metadata
+}
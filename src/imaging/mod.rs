@@ -0,0 +1,57 @@
+// imaging/mod.rs - Image loading, saving and processing shared by the CLI and the GTK ui
+// Copyright (C) 2019 Denis Karpovskiy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+pub mod analysis;
+pub mod binary;
+pub mod color;
+pub mod components;
+pub mod contour;
+pub mod draw;
+pub mod filters;
+pub mod geometry;
+pub mod graph;
+pub mod loader;
+pub mod medial_axis;
+pub mod metadata;
+pub mod morphology;
+pub mod prune;
+pub mod roi;
+pub mod skeleton;
+pub mod threshold;
+pub mod vector;
+pub mod writer;
+
+pub use analysis::{ SkeletonAnalysis, analyze_skeleton };
+pub use binary::{ BinaryGrid, count_foreground, is_binary };
+pub use color::{ invert, parse_color };
+pub use components::{ ComponentStats, Components, stats_to_csv };
+pub use contour::{ ContourFormat, ContourKind, Contours, trace_contours };
+pub use draw::{ brush_offsets, draw_line, flood_fill, line_points };
+pub use filters::{ ChannelWeights, PreprocessFilter };
+pub use geometry::{ FlipDirection, GeometricTransform, deskew, flip, resample, rotate, skew_angle };
+pub use graph::{ GraphFormat, NodeKind, PixelKind, SkeletonGraph, classify_pixel, trace_graph };
+pub use loader::{ Document, LoadOptions, PageRange, load_document };
+pub use medial_axis::{ DistanceMap, MedialAxis, MedialPoint, distance_transform, medial_axis };
+pub use metadata::{ ImageMetadata, embed_metadata };
+pub use morphology::{
+    ElementShape, MorphologyOperation, StructuringElement, fill_small_holes, morphology, remove_small_components
+};
+pub use prune::prune_spurs;
+pub use roi::{ Roi, process_region };
+pub use skeleton::SkeletonizerVariant;
+pub use threshold::{ Channel, ThresholdMode };
+pub use vector::{ VectorFormat, VectorOptions, encode_vector };
+pub use writer::{ save_pages, encode_pages, encode_image, encode_png, encode_tiff_pages };
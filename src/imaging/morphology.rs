@@ -0,0 +1,235 @@
+// morphology.rs - Binary morphology and noise removal
+// Copyright (C) 2019 Denis Karpovskiy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use improc_petrsu::AdjacencyMode;
+use super::binary::BinaryGrid;
+use super::components::{ self, Components };
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ElementShape {
+    Square,
+    Cross,
+    Disk
+}
+
+impl ElementShape {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "square" => Some(ElementShape::Square),
+            "cross" => Some(ElementShape::Cross),
+            "disk" => Some(ElementShape::Disk),
+            _ => None
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ElementShape::Square => "square",
+            ElementShape::Cross => "cross",
+            ElementShape::Disk => "disk"
+        }
+    }
+}
+
+/// A structuring element centred on the pixel, `radius` 1 gives a 3x3 element
+#[derive(Clone, Copy, Debug)]
+pub struct StructuringElement {
+    pub shape: ElementShape,
+    pub radius: u32
+}
+
+impl StructuringElement {
+    fn offsets(&self) -> Vec<(i64, i64)> {
+        let radius = self.radius as i64;
+        let mut offsets = Vec::new();
+        for dy in -radius..=radius {
+            for dx in -radius..=radius {
+                let inside = match self.shape {
+                    ElementShape::Square => true,
+                    ElementShape::Cross => dx == 0 || dy == 0,
+                    ElementShape::Disk => dx * dx + dy * dy <= radius * radius
+                };
+                if inside {
+                    offsets.push((dx, dy));
+                }
+            }
+        }
+        offsets
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MorphologyOperation {
+    Erode,
+    Dilate,
+    Open,
+    Close
+}
+
+impl MorphologyOperation {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "erode" => Some(MorphologyOperation::Erode),
+            "dilate" => Some(MorphologyOperation::Dilate),
+            "open" => Some(MorphologyOperation::Open),
+            "close" => Some(MorphologyOperation::Close),
+            _ => None
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            MorphologyOperation::Erode => "erode",
+            MorphologyOperation::Dilate => "dilate",
+            MorphologyOperation::Open => "open",
+            MorphologyOperation::Close => "close"
+        }
+    }
+}
+
+// Sets the pixel if any (dilation) or every (erosion) pixel under the element is set.
+// Everything outside the grid is ignored, so shapes touching the border don't erode
+fn filter(grid: &BinaryGrid, element: &StructuringElement, dilation: bool) -> BinaryGrid {
+    let offsets = element.offsets();
+    let (width, height) = (grid.width() as i64, grid.height() as i64);
+    let mut result = BinaryGrid::new(grid.width(), grid.height());
+
+    for y in 0..grid.height() {
+        for x in 0..grid.width() {
+            let mut covered = offsets.iter().map(|&(dx, dy)| {
+                let (x, y) = (x as i64 + dx, y as i64 + dy);
+                if x < 0 || y < 0 || x >= width || y >= height {
+                    !dilation
+                } else {
+                    grid.at(x as u32, y as u32)
+                }
+            });
+            let value = if dilation {
+                covered.any(|pixel| pixel)
+            } else {
+                covered.all(|pixel| pixel)
+            };
+            result.set(x, y, value);
+        }
+    }
+
+    result
+}
+
+pub fn erode(grid: &BinaryGrid, element: &StructuringElement) -> BinaryGrid {
+    filter(grid, element, false)
+}
+
+pub fn dilate(grid: &BinaryGrid, element: &StructuringElement) -> BinaryGrid {
+    filter(grid, element, true)
+}
+
+pub fn morphology(grid: &BinaryGrid, operation: MorphologyOperation, element: &StructuringElement) -> BinaryGrid {
+    match operation {
+        MorphologyOperation::Erode => erode(grid, element),
+        MorphologyOperation::Dilate => dilate(grid, element),
+        MorphologyOperation::Open => dilate(&erode(grid, element), element),
+        MorphologyOperation::Close => erode(&dilate(grid, element), element)
+    }
+}
+
+/// Clears the foreground components smaller than `min_area` pixels
+pub fn remove_small_components(grid: &BinaryGrid, min_area: usize, adjacency: AdjacencyMode) -> BinaryGrid {
+    let components = Components::label(grid, adjacency);
+    let areas = components.areas();
+
+    let mut result = grid.clone();
+    for (x, y) in grid.foreground() {
+        if areas[components.label_at(x, y) as usize] < min_area {
+            result.set(x, y, false);
+        }
+    }
+    result
+}
+
+/// Fills the holes smaller than `max_area` pixels, the background touching
+/// the border of the image isn't a hole
+pub fn fill_small_holes(grid: &BinaryGrid, max_area: usize, adjacency: AdjacencyMode) -> BinaryGrid {
+    let background = grid.inverted();
+    let components = Components::label(&background, components::complementary(adjacency));
+    let areas = components.areas();
+
+    let mut is_hole = vec![true; components.count() + 1];
+    for (x, y) in background.foreground() {
+        if x == 0 || y == 0 || x == grid.width() - 1 || y == grid.height() - 1 {
+            is_hole[components.label_at(x, y) as usize] = false;
+        }
+    }
+
+    let mut result = grid.clone();
+    for (x, y) in background.foreground() {
+        let label = components.label_at(x, y) as usize;
+        if is_hole[label] && areas[label] < max_area {
+            result.set(x, y, true);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SQUARE: StructuringElement = StructuringElement { shape: ElementShape::Square, radius: 1 };
+
+    // a 3x3 block in the top left corner of a 6x6 grid
+    fn corner_block() -> BinaryGrid {
+        let mut grid = BinaryGrid::new(6, 6);
+        for y in 0..3 {
+            for x in 0..3 {
+                grid.set(x, y, true);
+            }
+        }
+        grid
+    }
+
+    #[test]
+    fn erosion_keeps_shapes_touching_the_border() {
+        let eroded = erode(&corner_block(), &SQUARE);
+        for y in 0..6 {
+            for x in 0..6 {
+                assert_eq!(eroded.at(x, y), x < 2 && y < 2, "pixel ({}, {})", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn erosion_of_a_full_grid_is_full() {
+        let mut grid = BinaryGrid::new(4, 3);
+        for y in 0..3 {
+            for x in 0..4 {
+                grid.set(x, y, true);
+            }
+        }
+        assert_eq!(erode(&grid, &SQUARE).count(), 12);
+    }
+
+    #[test]
+    fn closing_is_extensive_along_the_border() {
+        let grid = corner_block();
+        let closed = morphology(&grid, MorphologyOperation::Close, &SQUARE);
+        for y in 0..6 {
+            for x in 0..6 {
+                assert_eq!(closed.at(x, y), grid.at(x, y), "pixel ({}, {})", x, y);
+            }
+        }
+    }
+}
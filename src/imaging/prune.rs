@@ -0,0 +1,47 @@
+// prune.rs - Removes parasitic branches from skeletons
+// Copyright (C) 2019 Denis Karpovskiy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use super::binary::BinaryGrid;
+use super::graph::{ self, NodeKind };
+
+/// Removes the branches that run from a junction to an endpoint and are
+/// shorter than `min_length` pixels. Runs a single pass, so the branches
+/// that become spurs after the pruning stay.
+pub fn prune_spurs(skeleton: &BinaryGrid, min_length: usize) -> BinaryGrid {
+    let graph = graph::trace_graph(skeleton);
+    let mut pruned = skeleton.clone();
+
+    for edge in &graph.edges {
+        let (source, target) = (&graph.nodes[edge.source], &graph.nodes[edge.target]);
+        let junction = match (source.kind, target.kind) {
+            (NodeKind::Junction, NodeKind::Endpoint) => source,
+            (NodeKind::Endpoint, NodeKind::Junction) => target,
+            _ => continue
+        };
+
+        if edge.length >= min_length as f64 {
+            continue;
+        }
+
+        for point in &edge.points {
+            if !junction.pixels.contains(point) {
+                pruned.set(point.0, point.1, false);
+            }
+        }
+    }
+
+    pruned
+}
@@ -0,0 +1,106 @@
+// roi.rs - Processing restricted to a rectangular region of the image
+// Copyright (C) 2019 Denis Karpovskiy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use image::{ imageops, DynamicImage, GenericImageView, Pixel, RgbImage };
+use std::fmt;
+use std::str::FromStr;
+
+/// A rectangle in pixels, written as "x,y,width,height"
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Roi {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32
+}
+
+impl FromStr for Roi {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let values = s
+            .split(',')
+            .map(|value| value.trim().parse::<u32>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| format!("Expected x,y,width,height in pixels, got '{}'", s))?;
+
+        match values.as_slice() {
+            &[x, y, width, height] if width > 0 && height > 0 => Ok(Roi { x, y, width, height }),
+            &[_, _, _, _] => Err("The region must not be empty".to_string()),
+            _ => Err(format!("Expected x,y,width,height in pixels, got '{}'", s))
+        }
+    }
+}
+
+impl fmt::Display for Roi {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{},{},{},{}", self.x, self.y, self.width, self.height)
+    }
+}
+
+impl Roi {
+    /// The rectangle spanned by two corners in any order
+    pub fn from_corners((x0, y0): (u32, u32), (x1, y1): (u32, u32)) -> Self {
+        Roi {
+            x: x0.min(x1),
+            y: y0.min(y1),
+            width: (x1 as i64 - x0 as i64).abs() as u32 + 1,
+            height: (y1 as i64 - y0 as i64).abs() as u32 + 1
+        }
+    }
+
+    /// The part of the region inside the image, none when they don't overlap
+    pub fn clamp(&self, width: u32, height: u32) -> Option<Roi> {
+        if self.x >= width || self.y >= height {
+            return None;
+        }
+
+        Some(Roi {
+            x: self.x,
+            y: self.y,
+            width: self.width.min(width - self.x),
+            height: self.height.min(height - self.y)
+        })
+    }
+
+    pub fn crop(&self, image: &DynamicImage) -> DynamicImage {
+        DynamicImage::ImageRgb8(RgbImage::from_fn(self.width, self.height, |x, y| {
+            image.get_pixel(self.x + x, self.y + y).to_rgb()
+        }))
+    }
+
+    /// Replaces the region of the image with the processed one
+    pub fn paste(&self, image: &DynamicImage, region: &DynamicImage) -> DynamicImage {
+        let mut result = image.to_rgb();
+        imageops::replace(&mut result, &region.to_rgb(), self.x, self.y);
+        DynamicImage::ImageRgb8(result)
+    }
+}
+
+/// Applies the operation to the region only, the rest of the image stays as it is.
+/// With `crop` the result is the processed region alone. The region has to lie
+/// inside the image, see `Roi::clamp`.
+pub fn process_region<F, E>(image: DynamicImage, roi: Roi, crop: bool, operation: F) -> Result<DynamicImage, E>
+where
+    F: FnOnce(DynamicImage) -> Result<DynamicImage, E>
+{
+    let region = operation(roi.crop(&image))?;
+    if crop {
+        Ok(region)
+    } else {
+        Ok(roi.paste(&image, &region))
+    }
+}
@@ -0,0 +1,59 @@
+// skeleton.rs - The skeletonization algorithms of the library
+// Copyright (C) 2019 Denis Karpovskiy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use improc_petrsu::{
+    AdjacencyMode,
+    EberlySkeletonizer,
+    RosenfeldSkeletonizer,
+    Skeletonizer,
+    ZhangSuenSkeletonizer
+};
+
+/// Every skeletonizer of the library together with its settings
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SkeletonizerVariant {
+    RosenfeldFour,
+    RosenfeldEight,
+    Eberly,
+    ZhangSuen
+}
+
+impl SkeletonizerVariant {
+    pub const ALL: [SkeletonizerVariant; 4] = [
+        SkeletonizerVariant::RosenfeldFour,
+        SkeletonizerVariant::RosenfeldEight,
+        SkeletonizerVariant::Eberly,
+        SkeletonizerVariant::ZhangSuen
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SkeletonizerVariant::RosenfeldFour => "Rosenfeld (four-adjacency)",
+            SkeletonizerVariant::RosenfeldEight => "Rosenfeld (eight-adjacency)",
+            SkeletonizerVariant::Eberly => "Eberly",
+            SkeletonizerVariant::ZhangSuen => "Zhang Suen"
+        }
+    }
+
+    pub fn create(&self) -> Box<dyn Skeletonizer> {
+        match self {
+            SkeletonizerVariant::RosenfeldFour => Box::new(RosenfeldSkeletonizer::new(AdjacencyMode::Four)),
+            SkeletonizerVariant::RosenfeldEight => Box::new(RosenfeldSkeletonizer::new(AdjacencyMode::Eight)),
+            SkeletonizerVariant::Eberly => Box::new(EberlySkeletonizer::new()),
+            SkeletonizerVariant::ZhangSuen => Box::new(ZhangSuenSkeletonizer::new())
+        }
+    }
+}
@@ -0,0 +1,133 @@
+// threshold.rs - Binarization on a colour channel or a colour range
+// Copyright (C) 2019 Denis Karpovskiy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use image::{ DynamicImage, Rgb, RgbImage };
+
+/// A channel of the RGB or the HSV colour model, scaled to 0..255
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Channel {
+    Red,
+    Green,
+    Blue,
+    Hue,
+    Saturation,
+    Value
+}
+
+impl Channel {
+    /// Accepts both the full names and the letters R, G, B, H, S and V
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "r" | "red" => Some(Channel::Red),
+            "g" | "green" => Some(Channel::Green),
+            "b" | "blue" => Some(Channel::Blue),
+            "h" | "hue" => Some(Channel::Hue),
+            "s" | "saturation" => Some(Channel::Saturation),
+            "v" | "value" => Some(Channel::Value),
+            _ => None
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Channel::Red => "red",
+            Channel::Green => "green",
+            Channel::Blue => "blue",
+            Channel::Hue => "hue",
+            Channel::Saturation => "saturation",
+            Channel::Value => "value"
+        }
+    }
+
+    pub fn value(&self, pixel: &Rgb<u8>) -> u8 {
+        let [red, green, blue] = pixel.0;
+        let max = red.max(green).max(blue);
+        let min = red.min(green).min(blue);
+        match self {
+            Channel::Red => red,
+            Channel::Green => green,
+            Channel::Blue => blue,
+            Channel::Value => max,
+            Channel::Saturation if max == 0 => 0,
+            Channel::Saturation => ((max - min) as u32 * 255 / max as u32) as u8,
+            Channel::Hue if max == min => 0,
+            Channel::Hue => {
+                let delta = (max - min) as f64;
+                let (r, g, b) = (red as f64, green as f64, blue as f64);
+                let sector = if max == red {
+                    ((g - b) / delta).rem_euclid(6.0)
+                } else if max == green {
+                    (b - r) / delta + 2.0
+                } else {
+                    (r - g) / delta + 4.0
+                };
+                // the full circle is 256 steps, so 360 degrees wrap around to 0
+                ((sector * 256.0 / 6.0).round() as u32 % 256) as u8
+            }
+        }
+    }
+}
+
+/// How the pixels of a colour image are split into foreground and background
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ThresholdMode {
+    /// The pixels below the threshold in the channel are foreground,
+    /// or the ones at and above it with `above`
+    Channel { channel: Channel, threshold: u8, above: bool },
+    /// The pixels within the Euclidean RGB distance of the colour are foreground
+    ColorRange { color: Rgb<u8>, distance: f64 }
+}
+
+impl ThresholdMode {
+    pub fn is_foreground(&self, pixel: &Rgb<u8>) -> bool {
+        match *self {
+            ThresholdMode::Channel { channel, threshold, above } => (channel.value(pixel) >= threshold) == above,
+            ThresholdMode::ColorRange { color, distance } => {
+                let squared: f64 = pixel.0
+                    .iter()
+                    .zip(color.0.iter())
+                    .map(|(&a, &b)| (a as f64 - b as f64).powi(2))
+                    .sum();
+                squared <= distance * distance
+            }
+        }
+    }
+
+    /// Renders the foreground black on white
+    pub fn apply(&self, image: &DynamicImage) -> RgbImage {
+        let mut result = image.to_rgb();
+        for pixel in result.pixels_mut() {
+            *pixel = if self.is_foreground(pixel) {
+                Rgb([0, 0, 0])
+            } else {
+                Rgb([255, 255, 255])
+            };
+        }
+        result
+    }
+
+    /// The entry of the processing history
+    pub fn description(&self) -> String {
+        match self {
+            ThresholdMode::Channel { channel, threshold, above } => format!(
+                "Converted to binary on the {} channel with threshold: {}{}",
+                channel.name(), threshold, if *above { ", keeping the values above it" } else { "" }),
+            ThresholdMode::ColorRange { color, distance } => format!(
+                "Converted to binary keeping the colours within {} of #{:02x}{:02x}{:02x}",
+                distance, color[0], color[1], color[2])
+        }
+    }
+}
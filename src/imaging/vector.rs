@@ -0,0 +1,166 @@
+// vector.rs - Exports skeletons as SVG and DXF polylines
+// Copyright (C) 2019 Denis Karpovskiy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use super::graph::SkeletonGraph;
+
+const MM_PER_INCH: f64 = 25.4;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum VectorFormat {
+    Svg,
+    Dxf
+}
+
+impl VectorFormat {
+    /// Parses "svg" or "dxf", also used for file extensions
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "svg" => Some(VectorFormat::Svg),
+            "dxf" => Some(VectorFormat::Dxf),
+            _ => None
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct VectorOptions {
+    /// Douglas-Peucker tolerance in pixels, 0 keeps every point
+    pub tolerance: f64,
+    /// Converts the pixels to millimetres, without it the units are pixels
+    pub dpi: Option<f64>
+}
+
+impl Default for VectorOptions {
+    fn default() -> Self {
+        VectorOptions {
+            tolerance: 1.0,
+            dpi: None
+        }
+    }
+}
+
+fn distance_to_segment(point: (f64, f64), start: (f64, f64), end: (f64, f64)) -> f64 {
+    let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+    let length_squared = dx * dx + dy * dy;
+    if length_squared == 0.0 {
+        return (point.0 - start.0).hypot(point.1 - start.1);
+    }
+
+    let t = (((point.0 - start.0) * dx + (point.1 - start.1) * dy) / length_squared).clamp(0.0, 1.0);
+    (point.0 - start.0 - t * dx).hypot(point.1 - start.1 - t * dy)
+}
+
+/// Douglas-Peucker simplification, the first and the last points are kept
+pub fn simplify(points: &[(f64, f64)], tolerance: f64) -> Vec<(f64, f64)> {
+    if points.len() < 3 || tolerance <= 0.0 {
+        return points.to_vec();
+    }
+
+    let mut keep = vec![false; points.len()];
+    keep[0] = true;
+    keep[points.len() - 1] = true;
+
+    let mut ranges = vec![(0, points.len() - 1)];
+    while let Some((first, last)) = ranges.pop() {
+        let farthest = (first + 1..last)
+            .map(|index| (index, distance_to_segment(points[index], points[first], points[last])))
+            .fold(None, |farthest: Option<(usize, f64)>, candidate| match farthest {
+                Some(farthest) if farthest.1 >= candidate.1 => Some(farthest),
+                _ => Some(candidate)
+            });
+
+        if let Some((index, distance)) = farthest {
+            if distance > tolerance {
+                keep[index] = true;
+                ranges.push((first, index));
+                ranges.push((index, last));
+            }
+        }
+    }
+
+    points
+        .iter()
+        .zip(keep)
+        .filter(|&(_, keep)| keep)
+        .map(|(&point, _)| point)
+        .collect()
+}
+
+/// Every edge of the graph as a simplified polyline in pixel coordinates
+pub fn polylines(graph: &SkeletonGraph, tolerance: f64) -> Vec<Vec<(f64, f64)>> {
+    graph.edges
+        .iter()
+        .map(|edge| {
+            let points: Vec<(f64, f64)> = edge.points
+                .iter()
+                .map(|&(x, y)| (x as f64, y as f64))
+                .collect();
+            simplify(&points, tolerance)
+        })
+        .collect()
+}
+
+fn to_svg(polylines: &[Vec<(f64, f64)>], width: f64, height: f64, unit: &str, scale: f64) -> String {
+    let mut svg = format!(
+        concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}{unit}\" height=\"{h}{unit}\" viewBox=\"0 0 {w} {h}\">\n",
+            "  <g fill=\"none\" stroke=\"black\" stroke-width=\"{stroke}\" stroke-linecap=\"round\" stroke-linejoin=\"round\">\n"),
+        w = width * scale, h = height * scale, unit = unit, stroke = scale);
+
+    for polyline in polylines {
+        let points: Vec<String> = polyline
+            .iter()
+            .map(|&(x, y)| format!("{:.3},{:.3}", x * scale, y * scale))
+            .collect();
+        svg.push_str(&format!("    <polyline points=\"{}\"/>\n", points.join(" ")));
+    }
+
+    svg.push_str("  </g>\n</svg>\n");
+    svg
+}
+
+// An R12 drawing, the oldest version every CAD program reads
+fn to_dxf(polylines: &[Vec<(f64, f64)>], height: f64, millimetres: bool, scale: f64) -> String {
+    let mut dxf = String::from("0\nSECTION\n2\nHEADER\n9\n$INSUNITS\n70\n");
+    dxf.push_str(if millimetres { "4\n" } else { "0\n" });
+    dxf.push_str("0\nENDSEC\n0\nSECTION\n2\nENTITIES\n");
+
+    for polyline in polylines {
+        dxf.push_str("0\nPOLYLINE\n8\nSKELETON\n66\n1\n70\n0\n");
+        for &(x, y) in polyline {
+            // the y axis of DXF points up
+            dxf.push_str(&format!("0\nVERTEX\n8\nSKELETON\n10\n{:.4}\n20\n{:.4}\n30\n0.0\n",
+                x * scale, (height - y) * scale));
+        }
+        dxf.push_str("0\nSEQEND\n8\nSKELETON\n");
+    }
+
+    dxf.push_str("0\nENDSEC\n0\nEOF\n");
+    dxf
+}
+
+/// Writes the edges of the skeleton graph as polylines
+pub fn encode_vector(graph: &SkeletonGraph, format: VectorFormat, options: &VectorOptions) -> String {
+    let polylines = polylines(graph, options.tolerance);
+    let (width, height) = (graph.width as f64, graph.height as f64);
+    let scale = options.dpi.map_or(1.0, |dpi| MM_PER_INCH / dpi);
+
+    match format {
+        VectorFormat::Svg => to_svg(&polylines, width, height, if options.dpi.is_some() { "mm" } else { "" }, scale),
+        VectorFormat::Dxf => to_dxf(&polylines, height, options.dpi.is_some(), scale)
+    }
+}
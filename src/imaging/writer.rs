@@ -0,0 +1,175 @@
+// writer.rs - Saves single and multi-page images
+// Copyright (C) 2019 Denis Karpovskiy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use image::{
+    ColorType as ImageColorType, DynamicImage, ImageBuffer, ImageError, ImageFormat, ImageOutputFormat,
+    ImageResult, Luma, png::PngEncoder,
+    error::{ EncodingError, ImageFormatHint, ParameterError, ParameterErrorKind }
+};
+use std::fs;
+use std::io::{ Cursor, Seek, Write };
+use std::path::{ Path, PathBuf };
+use tiff::TiffResult;
+use tiff::encoder::{ TiffEncoder, TiffValue, Rational, colortype::{ self, ColorType } };
+use tiff::tags::{ ResolutionUnit, Tag };
+use super::metadata::{ self, ImageMetadata };
+
+pub fn encode_png(image: &DynamicImage) -> ImageResult<Vec<u8>> {
+    let mut bytes = Vec::new();
+    image.write_to(&mut bytes, ImageOutputFormat::Png)?;
+    Ok(bytes)
+}
+
+// `write_to` hands the samples to the encoder in native byte order, PNG wants them big endian
+fn encode_png16(image: &ImageBuffer<Luma<u16>, Vec<u16>>) -> ImageResult<Vec<u8>> {
+    let data: Vec<u8> = image.iter().flat_map(|sample| sample.to_be_bytes().to_vec()).collect();
+    let mut bytes = Vec::new();
+    PngEncoder::new(&mut bytes).encode(&data, image.width(), image.height(), ImageColorType::L16)?;
+    Ok(bytes)
+}
+
+/// Encodes the image and embeds as much of the metadata as the format allows
+pub fn encode_image(image: &DynamicImage, format: ImageFormat, metadata: &ImageMetadata) -> ImageResult<Vec<u8>> {
+    if format == ImageFormat::Tiff {
+        return encode_tiff_pages(std::slice::from_ref(image), metadata);
+    }
+
+    let bytes = match (format, image) {
+        (ImageFormat::Png, DynamicImage::ImageLuma16(gray)) => encode_png16(gray)?,
+        _ => {
+            let mut bytes = Vec::new();
+            image.write_to(&mut bytes, format)?;
+            bytes
+        }
+    };
+    Ok(metadata::embed_metadata(bytes, format, metadata))
+}
+
+fn tiff_error(err: tiff::TiffError) -> ImageError {
+    ImageError::Encoding(EncodingError::new(ImageFormatHint::Exact(ImageFormat::Tiff), err))
+}
+
+fn write_tiff_page<C, W>(
+    encoder: &mut TiffEncoder<W>,
+    width: u32,
+    height: u32,
+    data: &[C::Inner],
+    metadata: &ImageMetadata
+) -> TiffResult<()>
+where
+    C: ColorType,
+    W: Write + Seek,
+    [C::Inner]: TiffValue
+{
+    let mut image = encoder.new_image::<C>(width, height)?;
+
+    if let Some((x, y)) = metadata.dpi {
+        image.resolution_unit(ResolutionUnit::Inch);
+        image.x_resolution(Rational { n: (x * 100.0).round() as u32, d: 100 });
+        image.y_resolution(Rational { n: (y * 100.0).round() as u32, d: 100 });
+    }
+
+    if !metadata.history.is_empty() {
+        image.encoder().write_tag(Tag::ImageDescription, metadata.history_text().as_str())?;
+    }
+
+    let mut idx = 0;
+    while image.next_strip_sample_count() > 0 {
+        let sample_count = image.next_strip_sample_count() as usize;
+        image.write_strip(&data[idx..idx + sample_count])?;
+        idx += sample_count;
+    }
+
+    image.finish()
+}
+
+/// Encodes the pages as a single (possibly multi-page) TIFF file
+pub fn encode_tiff_pages(pages: &[DynamicImage], metadata: &ImageMetadata) -> ImageResult<Vec<u8>> {
+    let mut cursor = Cursor::new(Vec::new());
+    {
+        let mut encoder = TiffEncoder::new(&mut cursor).map_err(tiff_error)?;
+        for page in pages {
+            let result = match page {
+                DynamicImage::ImageLuma8(gray) => write_tiff_page::<colortype::Gray8, _>(
+                    &mut encoder, gray.width(), gray.height(), gray, metadata),
+                DynamicImage::ImageLuma16(gray) => write_tiff_page::<colortype::Gray16, _>(
+                    &mut encoder, gray.width(), gray.height(), gray, metadata),
+                DynamicImage::ImageLumaA8(_) | DynamicImage::ImageRgba8(_) => {
+                    let rgba = page.to_rgba();
+                    write_tiff_page::<colortype::RGBA8, _>(
+                        &mut encoder, rgba.width(), rgba.height(), &rgba, metadata)
+                },
+                _ => {
+                    let rgb = page.to_rgb();
+                    write_tiff_page::<colortype::RGB8, _>(
+                        &mut encoder, rgb.width(), rgb.height(), &rgb, metadata)
+                }
+            };
+            result.map_err(tiff_error)?;
+        }
+    }
+
+    Ok(cursor.into_inner())
+}
+
+/// Path for a single page when the output format can't hold several of them:
+/// "out.png" becomes "out-2.png" for the second page.
+pub fn page_path(path: &Path, index: usize) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(ext) => format!("{}-{}.{}", stem, index + 1, ext.to_string_lossy()),
+        None => format!("{}-{}", stem, index + 1)
+    };
+
+    path.with_file_name(name)
+}
+
+/// Encodes the pages into a single stream. Only TIFF can hold several pages,
+/// the other formats accept exactly one.
+pub fn encode_pages(pages: &[DynamicImage], format: ImageFormat, metadata: &ImageMetadata) -> ImageResult<Vec<u8>> {
+    match (format, pages) {
+        (ImageFormat::Tiff, _) => encode_tiff_pages(pages, metadata),
+        (_, [page]) => encode_image(page, format, metadata),
+        _ => Err(ImageError::Parameter(ParameterError::from_kind(ParameterErrorKind::Generic(
+            format!("{:?} can't hold {} pages, use TIFF instead", format, pages.len())))))
+    }
+}
+
+/// Saves the pages into a multi-page TIFF if the format (or the path extension
+/// when no format is given) is TIFF, otherwise every page goes into its own file.
+pub fn save_pages<P: AsRef<Path>>(
+    path: P,
+    pages: &[DynamicImage],
+    format: Option<ImageFormat>,
+    metadata: &ImageMetadata
+) -> ImageResult<()> {
+    let path = path.as_ref();
+    let format = match format {
+        Some(format) => format,
+        None => ImageFormat::from_path(path)?
+    };
+
+    if format == ImageFormat::Tiff || pages.len() == 1 {
+        fs::write(path, encode_pages(pages, format, metadata)?)?;
+        return Ok(());
+    }
+
+    for (index, page) in pages.iter().enumerate() {
+        fs::write(page_path(path, index), encode_image(page, format, metadata)?)?;
+    }
+
+    Ok(())
+}
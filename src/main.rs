@@ -17,16 +17,22 @@
 #![windows_subsystem = "windows"]
 mod gtk_ui;
 mod cli;
+mod imaging;
+mod profiler;
 
 use std::env;
 
+#[global_allocator]
+static ALLOCATOR: profiler::CountingAllocator = profiler::CountingAllocator;
+
 fn main() {
     env_logger::init();
     let args = env::args().collect::<Vec<_>>();
 
     if args.len() < 2 {
         gtk_ui::run_ui();
-    } else {
-        cli::run_ui();
+    } else if let Err(err) = cli::run_ui() {
+        eprintln!("{}", err);
+        std::process::exit(err.exit_code());
     }
 }
@@ -0,0 +1,111 @@
+// profiler.rs - Measures the wall time and the heap usage of processing stages
+// Copyright (C) 2019 Denis Karpovskiy
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::alloc::{ GlobalAlloc, Layout, System };
+use std::fmt;
+use std::sync::atomic::{ AtomicUsize, Ordering };
+use std::time::{ Duration, Instant };
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+/// The system allocator that keeps track of the current and the peak heap usage
+pub struct CountingAllocator;
+
+fn record_allocation(size: usize) {
+    let allocated = ALLOCATED.fetch_add(size, Ordering::Relaxed) + size;
+    PEAK.fetch_max(allocated, Ordering::Relaxed);
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            record_allocation(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            record_allocation(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            if new_size > layout.size() {
+                record_allocation(new_size - layout.size());
+            } else {
+                ALLOCATED.fetch_sub(layout.size() - new_size, Ordering::Relaxed);
+            }
+        }
+        new_ptr
+    }
+}
+
+/// Wall time and peak heap usage of a single stage
+#[derive(Clone, Copy, Debug)]
+pub struct StageProfile {
+    pub stage: &'static str,
+    pub wall_time: Duration,
+    /// The largest number of bytes the stage had allocated on the heap on top
+    /// of what was allocated when it started
+    pub peak_memory: usize
+}
+
+impl StageProfile {
+    pub fn milliseconds(&self) -> f64 {
+        self.wall_time.as_secs_f64() * 1000.0
+    }
+
+    pub fn peak_memory_mib(&self) -> f64 {
+        self.peak_memory as f64 / (1024.0 * 1024.0)
+    }
+}
+
+impl fmt::Display for StageProfile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {:.1} ms, {:.1} MiB", self.stage, self.milliseconds(), self.peak_memory_mib())
+    }
+}
+
+/// Runs the stage and measures it. A stage may run inside another one, the outer
+/// stage includes the memory of the inner one. The heap is shared by the whole
+/// process, so the allocations of other threads running meanwhile are counted too.
+pub fn profile<T, F: FnOnce() -> T>(stage: &'static str, f: F) -> (T, StageProfile) {
+    let allocated_at_start = ALLOCATED.load(Ordering::Relaxed);
+    // the peak of an enclosing stage so far, restored when this one ends
+    let enclosing_peak = PEAK.swap(allocated_at_start, Ordering::Relaxed);
+    let start = Instant::now();
+    let result = f();
+    let wall_time = start.elapsed();
+    let peak = PEAK.fetch_max(enclosing_peak, Ordering::Relaxed);
+
+    (result, StageProfile {
+        stage,
+        wall_time,
+        peak_memory: peak.saturating_sub(allocated_at_start)
+    })
+}